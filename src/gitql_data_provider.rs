@@ -1,20 +1,224 @@
+use crate::gitql_pushdown::CommitFilter;
 use gitql_core::object::Row;
 use gitql_core::values::{
-    boolean::BoolValue, datetime::DateTimeValue, integer::IntValue, null::NullValue,
-    text::TextValue, Value,
+    boolean::BoolValue, date::DateValue, datetime::DateTimeValue, float::FloatValue,
+    integer::IntValue, null::NullValue, text::TextValue, time::TimeValue, Value,
 };
 use gitql_engine::data_provider::DataProvider;
+use gix::bstr::ByteSlice;
 use gix::refs::Category;
+use nu_protocol::Signals;
+use std::collections::{BTreeMap, HashMap};
 use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 
 /// GitQL data provider backed by one or more local Git repositories.
 pub struct GitDataProvider {
     pub repos: Vec<gix::Repository>,
+    /// Revision spec (`HEAD~5`, `v1..v2`, `main...feature`) that bounds the
+    /// commit-walking tables. Walks from `HEAD` when unset.
+    pub revision: Option<String>,
+    /// Repository-relative file path for the tables that describe a single
+    /// file, such as `blame`.
+    pub path: Option<String>,
+    /// Read the reflog of every ref rather than only `HEAD`'s.
+    pub all_refs: bool,
+    /// Engine signals checked while walking history, so Ctrl-C stops a long
+    /// query instead of waiting for the whole table to be built.
+    pub signals: Signals,
+    /// `WHERE`/`LIMIT` bounds the commit-walking tables apply during the
+    /// walk, taken from the query being evaluated.
+    pub commit_filter: Option<CommitFilter>,
+    /// Most repositories read at the same time. Each worker thread opens its
+    /// own handle from a `gix::ThreadSafeRepository`.
+    pub threads: usize,
+    /// Skip repositories whose table can't be read, recording why in
+    /// `errors`, instead of failing the whole query.
+    pub keep_going: bool,
+    /// Repositories skipped by `keep_going`, shared like `timings`.
+    pub errors: Arc<Mutex<Vec<RepoError>>>,
+    /// One entry per table and repository read, shared so callers can
+    /// still read it after the provider is handed to the engine.
+    pub timings: Arc<Mutex<Vec<ProvideTiming>>>,
+}
+
+/// Time spent reading one table from one repository.
+#[derive(Clone, Debug)]
+pub struct ProvideTiming {
+    pub table: String,
+    pub repo: String,
+    pub rows: usize,
+    pub duration: Duration,
+}
+
+/// A repository skipped by `keep_going`, with the error that stopped it.
+/// `table` is unset when the repository couldn't be opened at all.
+#[derive(Clone, Debug)]
+pub struct RepoError {
+    pub repo: String,
+    pub table: Option<String>,
+    pub error: String,
 }
 
 impl GitDataProvider {
     pub fn new(repos: Vec<gix::Repository>) -> Self {
-        Self { repos }
+        Self {
+            repos,
+            revision: None,
+            path: None,
+            all_refs: false,
+            signals: Signals::empty(),
+            commit_filter: None,
+            threads: 1,
+            keep_going: false,
+            errors: Arc::default(),
+            timings: Arc::default(),
+        }
+    }
+
+    pub fn with_revision(mut self, revision: Option<String>) -> Self {
+        self.revision = revision;
+        self
+    }
+
+    pub fn with_path(mut self, path: Option<String>) -> Self {
+        self.path = path;
+        self
+    }
+
+    pub fn with_all_refs(mut self, all_refs: bool) -> Self {
+        self.all_refs = all_refs;
+        self
+    }
+
+    pub fn with_signals(mut self, signals: Signals) -> Self {
+        self.signals = signals;
+        self
+    }
+
+    pub fn with_commit_filter(mut self, commit_filter: Option<CommitFilter>) -> Self {
+        self.commit_filter = commit_filter;
+        self
+    }
+
+    pub fn with_threads(mut self, threads: usize) -> Self {
+        self.threads = threads.max(1);
+        self
+    }
+
+    /// Keep going past failing repositories, starting from the `errors` of
+    /// those that already failed to open.
+    pub fn with_keep_going(mut self, keep_going: bool, errors: Vec<RepoError>) -> Self {
+        self.keep_going = keep_going;
+        self.errors = Arc::new(Mutex::new(errors));
+        self
+    }
+
+    /// Rows read from one repository, or none when `keep_going` lets the
+    /// query carry on without it. Interrupts always stop the query.
+    fn rows_or_skip<T>(
+        &self,
+        table: &str,
+        repository: &gix::Repository,
+        rows: Result<Vec<T>, String>,
+    ) -> Result<Vec<T>, String> {
+        match rows {
+            Err(error) if self.keep_going && !self.signals.interrupted() => {
+                if let Ok(mut errors) = self.errors.lock() {
+                    errors.push(RepoError {
+                        repo: repo_workdir_path(repository),
+                        table: Some(table.to_string()),
+                        error,
+                    });
+                }
+                Ok(vec![])
+            }
+            rows => rows,
+        }
+    }
+
+    fn record_timing(
+        &self,
+        table: &str,
+        repository: &gix::Repository,
+        rows: usize,
+        duration: Duration,
+    ) {
+        if let Ok(mut timings) = self.timings.lock() {
+            timings.push(ProvideTiming {
+                table: table.to_string(),
+                repo: repo_workdir_path(repository),
+                rows,
+                duration,
+            });
+        }
+    }
+
+    fn table_options<'a>(&'a self, commit_filter: Option<&'a CommitFilter>) -> TableOptions<'a> {
+        TableOptions {
+            revision: self.revision.as_deref(),
+            path: self.path.as_deref(),
+            all_refs: self.all_refs,
+            commit_filter,
+            signals: &self.signals,
+        }
+    }
+
+    /// Read `table` from every repository on up to `threads` workers. Results
+    /// come back in repository order, whichever worker finishes first.
+    fn select_in_parallel(
+        &self,
+        table: &str,
+        selected_columns: &[String],
+        commit_filter: Option<&CommitFilter>,
+        threads: usize,
+    ) -> Vec<(Result<Vec<SendRow>, String>, Duration)> {
+        let shared: Vec<gix::ThreadSafeRepository> = self
+            .repos
+            .iter()
+            .map(|repository| repository.clone().into_sync())
+            .collect();
+        let options = self.table_options(commit_filter);
+        let next = AtomicUsize::new(0);
+
+        let mut results: Vec<_> = std::thread::scope(|scope| {
+            let workers: Vec<_> = (0..threads)
+                .map(|_| {
+                    scope.spawn(|| {
+                        let mut results = vec![];
+                        loop {
+                            let index = next.fetch_add(1, Ordering::Relaxed);
+                            let Some(shared) = shared.get(index) else {
+                                break;
+                            };
+                            let repository = shared.to_thread_local();
+                            let start = Instant::now();
+                            let rows =
+                                select_gql_objects(&repository, table, selected_columns, &options)
+                                    .and_then(|rows| rows.iter().map(SendRow::from_row).collect());
+                            results.push((index, rows, start.elapsed()));
+                        }
+                        results
+                    })
+                })
+                .collect();
+            workers
+                .into_iter()
+                .flat_map(|worker| {
+                    worker
+                        .join()
+                        .unwrap_or_else(|panic| std::panic::resume_unwind(panic))
+                })
+                .collect()
+        });
+
+        results.sort_by_key(|(index, _, _)| *index);
+        results
+            .into_iter()
+            .map(|(_, rows, duration)| (rows, duration))
+            .collect()
     }
 }
 
@@ -22,8 +226,28 @@ impl DataProvider for GitDataProvider {
     fn provide(&self, table: &str, selected_columns: &[String]) -> Result<Vec<Row>, String> {
         let mut rows: Vec<Row> = Vec::new();
 
+        let commit_filter = self
+            .commit_filter
+            .as_ref()
+            .filter(|commit_filter| commit_filter.table == table);
+        let threads = self.threads.min(self.repos.len());
+        if threads > 1 {
+            let results = self.select_in_parallel(table, selected_columns, commit_filter, threads);
+            for (repository, (repo_rows, duration)) in self.repos.iter().zip(results) {
+                let repo_rows = self.rows_or_skip(table, repository, repo_rows)?;
+                self.record_timing(table, repository, repo_rows.len(), duration);
+                rows.extend(repo_rows.into_iter().map(SendRow::into_row));
+            }
+            return Ok(rows);
+        }
+
+        let options = self.table_options(commit_filter);
         for repository in &self.repos {
-            rows.extend(select_gql_objects(repository, table, selected_columns)?);
+            let start = Instant::now();
+            let repo_rows = select_gql_objects(repository, table, selected_columns, &options);
+            let repo_rows = self.rows_or_skip(table, repository, repo_rows)?;
+            self.record_timing(table, repository, repo_rows.len(), start.elapsed());
+            rows.extend(repo_rows);
         }
 
         Ok(rows)
@@ -46,6 +270,79 @@ fn repo_name_from_path(repo_path: &str) -> String {
         .to_string()
 }
 
+/// A row as it crosses from a worker thread. GitQL values aren't `Send`, so
+/// workers hand over plain cells and the rows are rebuilt on the engine's
+/// thread.
+struct SendRow {
+    values: Vec<SendValue>,
+}
+
+enum SendValue {
+    Text(String),
+    Int(i64),
+    Float(f64),
+    Bool(bool),
+    Date(i64),
+    Time(String),
+    DateTime(i64),
+    Null,
+}
+
+impl SendRow {
+    /// Fails on value types a table cell can't hold, such as arrays, rather
+    /// than returning different cells than a single-threaded read would.
+    fn from_row(row: &Row) -> Result<SendRow, String> {
+        let values = row
+            .values
+            .iter()
+            .map(|value| {
+                let any = value.as_any();
+                if let Some(text) = any.downcast_ref::<TextValue>() {
+                    Ok(SendValue::Text(text.value.clone()))
+                } else if let Some(int) = any.downcast_ref::<IntValue>() {
+                    Ok(SendValue::Int(int.value))
+                } else if let Some(float) = any.downcast_ref::<FloatValue>() {
+                    Ok(SendValue::Float(float.value))
+                } else if let Some(boolean) = any.downcast_ref::<BoolValue>() {
+                    Ok(SendValue::Bool(boolean.value))
+                } else if let Some(date) = any.downcast_ref::<DateValue>() {
+                    Ok(SendValue::Date(date.timestamp))
+                } else if let Some(time) = any.downcast_ref::<TimeValue>() {
+                    Ok(SendValue::Time(time.value.clone()))
+                } else if let Some(datetime) = any.downcast_ref::<DateTimeValue>() {
+                    Ok(SendValue::DateTime(datetime.value))
+                } else if any.downcast_ref::<NullValue>().is_some() {
+                    Ok(SendValue::Null)
+                } else {
+                    Err(format!(
+                        "Can't read {} values on parallel threads, pass --threads 1",
+                        value.data_type().literal()
+                    ))
+                }
+            })
+            .collect::<Result<_, String>>()?;
+        Ok(SendRow { values })
+    }
+
+    fn into_row(self) -> Row {
+        let values = self
+            .values
+            .into_iter()
+            .map(|value| match value {
+                SendValue::Text(text) => text_value(text),
+                SendValue::Int(int) => int_value(int),
+                SendValue::Float(value) => Box::new(FloatValue { value }),
+                SendValue::Bool(boolean) => bool_value(boolean),
+                SendValue::Date(timestamp) => Box::new(DateValue { timestamp }),
+                SendValue::Time(value) => Box::new(TimeValue { value }),
+                SendValue::DateTime(datetime) => Box::new(DateTimeValue { value: datetime }),
+                SendValue::Null => null_value(),
+            })
+            .collect();
+        Row { values }
+    }
+}
+
 fn text_value(value: impl ToString) -> Box<dyn Value> {
     Box::new(TextValue {
         value: value.to_string(),
@@ -76,17 +373,181 @@ fn repo_metadata_value(
     }
 }
 
+/// Start a revision walk over the commits selected by `revision`, or over the
+/// ancestors of `HEAD` when no revision was requested.
+fn revision_walk<'repo>(
+    repo: &'repo gix::Repository,
+    revision: Option<&str>,
+) -> Result<gix::revision::Walk<'repo>, String> {
+    let (tips, hidden) = revision_tips(repo, revision)?;
+    repo.rev_walk(tips)
+        .with_hidden(hidden)
+        .all()
+        .map_err(|err| err.to_string())
+}
+
+/// Commits a walk over `revision` starts from, and the commits it must not
+/// go past.
+fn revision_tips(
+    repo: &gix::Repository,
+    revision: Option<&str>,
+) -> Result<(Vec<gix::ObjectId>, Vec<gix::ObjectId>), String> {
+    use gix::revision::plumbing::Spec;
+
+    let Some(revision) = revision else {
+        let head = repo.head_id().map_err(|err| err.to_string())?.detach();
+        return Ok((vec![head], vec![]));
+    };
+
+    let peel_to_commit = |id: gix::ObjectId| -> Result<gix::ObjectId, String> {
+        repo.find_object(id)
+            .map_err(|err| err.to_string())?
+            .peel_to_commit()
+            .map(|commit| commit.id)
+            .map_err(|err| err.to_string())
+    };
+    let parents_of = |id: gix::ObjectId| -> Result<Vec<gix::ObjectId>, String> {
+        Ok(repo
+            .find_commit(id)
+            .map_err(|err| err.to_string())?
+            .parent_ids()
+            .map(|id| id.detach())
+            .collect())
+    };
+
+    let spec = repo
+        .rev_parse(revision)
+        .map_err(|err| format!("Invalid revision '{revision}': {err}"))?
+        .detach();
+    Ok(match spec {
+        Spec::Include(id) => (vec![peel_to_commit(id)?], vec![]),
+        Spec::Exclude(id) => (
+            vec![repo.head_id().map_err(|err| err.to_string())?.detach()],
+            vec![peel_to_commit(id)?],
+        ),
+        Spec::Range { from, to } => (vec![peel_to_commit(to)?], vec![peel_to_commit(from)?]),
+        Spec::Merge { theirs, ours } => {
+            let theirs = peel_to_commit(theirs)?;
+            let ours = peel_to_commit(ours)?;
+            let merge_bases = repo
+                .merge_bases_many(theirs, &[ours])
+                .map_err(|err| err.to_string())?
+                .into_iter()
+                .map(|id| id.detach())
+                .collect();
+            (vec![theirs, ours], merge_bases)
+        }
+        Spec::IncludeOnlyParents(id) => (parents_of(peel_to_commit(id)?)?, vec![]),
+        Spec::ExcludeParents(id) => {
+            let id = peel_to_commit(id)?;
+            (vec![id], parents_of(id)?)
+        }
+    })
+}
+
+/// Resolve `revision` to the single commit it names, or to `HEAD` when unset.
+fn resolve_commit_id(
+    repo: &gix::Repository,
+    revision: Option<&str>,
+) -> Result<gix::ObjectId, String> {
+    let Some(revision) = revision else {
+        return repo
+            .head_id()
+            .map(|id| id.detach())
+            .map_err(|err| err.to_string());
+    };
+
+    repo.rev_parse_single(revision)
+        .map_err(|err| format!("Invalid revision '{revision}': {err}"))?
+        .object()
+        .map_err(|err| err.to_string())?
+        .peel_to_commit()
+        .map(|commit| commit.id)
+        .map_err(|err| err.to_string())
+}
+
+/// Whether a walked commit can pass the pushed-down `commit_id` bound,
+/// checked before the commit is decoded.
+fn matches_commit_id(
+    commit_filter: Option<&CommitFilter>,
+    commit_info: &gix::revision::walk::Info<'_>,
+) -> bool {
+    commit_filter
+        .is_none_or(|commit_filter| commit_filter.matches_commit_id(&commit_info.id.to_string()))
+}
+
+/// Whether a decoded commit passes the pushed-down time and author bounds.
+fn matches_commit(
+    commit_filter: Option<&CommitFilter>,
+    datetime: i64,
+    commit: &gix::objs::CommitRef<'_>,
+) -> bool {
+    commit_filter.is_none_or(|commit_filter| {
+        let author_email = commit
+            .author()
+            .ok()
+            .map(|author| author.email.to_str_lossy().into_owned());
+        commit_filter.matches_commit(datetime, author_email.as_deref())
+    })
+}
+
+/// Error out of a table walk once the engine has been interrupted.
+fn check_interrupted(signals: &Signals) -> Result<(), String> {
+    if signals.interrupted() {
+        Err("Query interrupted".to_string())
+    } else {
+        Ok(())
+    }
+}
+
+/// Number of commits in `revwalk`, stopping early when interrupted.
+fn count_walk(revwalk: gix::revision::Walk<'_>, signals: &Signals) -> Result<usize, String> {
+    let mut count = 0;
+    for _ in revwalk {
+        check_interrupted(signals)?;
+        count += 1;
+    }
+    Ok(count)
+}
+
+/// The provider's settings for one `provide` call, as the table readers
+/// take them.
+struct TableOptions<'a> {
+    revision: Option<&'a str>,
+    path: Option<&'a str>,
+    all_refs: bool,
+    /// Pushed-down bounds, only set for the table they were read for.
+    commit_filter: Option<&'a CommitFilter>,
+    signals: &'a Signals,
+}
+
 fn select_gql_objects(
     repo: &gix::Repository,
     table: &str,
     selected_columns: &[String],
+    options: &TableOptions<'_>,
 ) -> Result<Vec<Row>, String> {
+    let TableOptions {
+        revision,
+        path,
+        all_refs,
+        commit_filter,
+        signals,
+    } = *options;
     match table {
         "refs" => select_references(repo, selected_columns),
-        "commits" => select_commits(repo, selected_columns),
-        "branches" => select_branches(repo, selected_columns),
-        "diffs" => select_diffs(repo, selected_columns),
+        "commits" => select_commits(repo, selected_columns, revision, commit_filter, signals),
+        "branches" => select_branches(repo, selected_columns, signals),
+        "diffs" => select_diffs(repo, selected_columns, revision, commit_filter, signals),
+        "diff_files" => select_diff_files(repo, selected_columns, revision, commit_filter, signals),
         "tags" => select_tags(repo, selected_columns),
+        "blame" => select_blame(repo, selected_columns, revision, path),
+        "files" => select_files(repo, selected_columns, revision),
+        "status" => select_status(repo, selected_columns),
+        "remotes" => select_remotes(repo, selected_columns),
+        "stashes" => select_stashes(repo, selected_columns),
+        "reflog" => select_reflog(repo, selected_columns, all_refs),
+        "worktrees" => select_worktrees(repo, selected_columns),
         _ => Ok(vec![Row { values: vec![] }]),
     }
 }
@@ -141,20 +602,37 @@ fn select_references(
     Ok(rows)
 }
 
-fn select_commits(repo: &gix::Repository, selected_columns: &[String]) -> Result<Vec<Row>, String> {
-    let head_id = repo.head_id().map_err(|err| err.to_string())?;
+fn select_commits(
+    repo: &gix::Repository,
+    selected_columns: &[String],
+    revision: Option<&str>,
+    commit_filter: Option<&CommitFilter>,
+    signals: &Signals,
+) -> Result<Vec<Row>, String> {
     let repo_path = repo_workdir_path(repo);
     let repo_name = repo_name_from_path(&repo_path);
-    let revwalk = head_id.ancestors().all().map_err(|err| err.to_string())?;
+    let revwalk = revision_walk(repo, revision)?;
     let mut rows: Vec<Row> = Vec::new();
 
     for commit_info in revwalk {
+        check_interrupted(signals)?;
         let commit_info = commit_info.map_err(|err| err.to_string())?;
+        if !matches_commit_id(commit_filter, &commit_info) {
+            continue;
+        }
         let commit = repo
             .find_object(commit_info.id)
             .map_err(|err| err.to_string())?
             .into_commit();
         let commit = commit.decode().map_err(|err| err.to_string())?;
+        let datetime = commit_info
+            .commit_time
+            .unwrap_or_else(|| commit.time().map(|time| time.seconds).unwrap_or(0));
+        // Skip rather than stop the walk on an old commit: commit dates aren't
+        // ordered along history, so its ancestors can still be newer.
+        if !matches_commit(commit_filter, datetime, &commit) {
+            continue;
+        }
 
         let mut values: Vec<Box<dyn Value>> = Vec::with_capacity(selected_columns.len());
         for column_name in selected_columns {
@@ -187,11 +665,7 @@ fn select_commits(repo: &gix::Repository, selected_columns: &[String]) -> Result
                 ),
                 "title" => text_value(commit.message().summary().to_string()),
                 "message" => text_value(commit.message.to_string()),
-                "datetime" => Box::new(DateTimeValue {
-                    value: commit_info
-                        .commit_time
-                        .unwrap_or_else(|| commit.time().map(|time| time.seconds).unwrap_or(0)),
-                }),
+                "datetime" => Box::new(DateTimeValue { value: datetime }),
                 "parents_count" => int_value(commit.parents.len() as i64),
                 _ => repo_metadata_value(column_name, &repo_path, &repo_name)
                     .unwrap_or_else(null_value),
@@ -201,6 +675,9 @@ fn select_commits(repo: &gix::Repository, selected_columns: &[String]) -> Result
 
         let row = Row { values };
         rows.push(row);
+        if commit_filter.is_some_and(|commit_filter| commit_filter.is_exhausted(rows.len())) {
+            break;
+        }
     }
 
     Ok(rows)
@@ -209,6 +686,7 @@ fn select_commits(repo: &gix::Repository, selected_columns: &[String]) -> Result
 fn select_branches(
     repo: &gix::Repository,
     selected_columns: &[String],
+    signals: &Signals,
 ) -> Result<Vec<Row>, String> {
     let mut rows: Vec<Row> = vec![];
 
@@ -222,8 +700,25 @@ fn select_branches(
         Some(head_ref) => head_ref,
         None => return Ok(rows),
     };
+    let head_id = repo.head_id().ok().map(|id| id.detach());
+    let select_upstream = selected_columns.iter().any(|column| {
+        matches!(
+            column.as_str(),
+            "upstream" | "ahead" | "behind" | "merge_base_id"
+        )
+    });
 
     for mut branch in local_and_remote_branches.flatten() {
+        check_interrupted(signals)?;
+        let branch_id = branch.try_id().map(|id| id.detach());
+        let upstream = if select_upstream {
+            branch_id.and_then(|id| branch_upstream_status(repo, branch.name(), id, signals))
+        } else {
+            None
+        };
+        // An interrupted ahead/behind count is incomplete, so stop here
+        // rather than report it.
+        check_interrupted(signals)?;
         let mut values: Vec<Box<dyn Value>> = Vec::with_capacity(selected_columns.len());
 
         for column_name in selected_columns {
@@ -231,10 +726,10 @@ fn select_branches(
             let value = match column_name {
                 "name" => text_value(branch.name().as_bstr().to_string()),
                 "commit_count" => int_value(
-                    branch
-                        .try_id()
-                        .and_then(|id| id.ancestors().all().ok())
-                        .map_or(-1, |revwalk| revwalk.count() as i64),
+                    match branch.try_id().and_then(|id| id.ancestors().all().ok()) {
+                        Some(revwalk) => count_walk(revwalk, signals)? as i64,
+                        None => -1,
+                    },
                 ),
                 "updated" => {
                     let timestamp = branch
@@ -264,6 +759,26 @@ fn select_branches(
                     branch.name().category(),
                     Some(Category::RemoteBranch)
                 )),
+                "upstream" => upstream
+                    .as_ref()
+                    .map_or_else(null_value, |upstream| text_value(&upstream.name)),
+                "ahead" => upstream
+                    .as_ref()
+                    .and_then(|upstream| upstream.ahead)
+                    .map_or_else(null_value, |ahead| int_value(ahead as i64)),
+                "behind" => upstream
+                    .as_ref()
+                    .and_then(|upstream| upstream.behind)
+                    .map_or_else(null_value, |behind| int_value(behind as i64)),
+                "merge_base_id" => upstream
+                    .as_ref()
+                    .and_then(|upstream| upstream.merge_base)
+                    .map_or_else(null_value, text_value),
+                "is_merged_into_head" => bool_value(
+                    branch_id
+                        .zip(head_id)
+                        .is_some_and(|(id, head_id)| is_ancestor(repo, id, head_id)),
+                ),
                 _ => repo_metadata_value(column_name, &repo_path, &repo_name)
                     .unwrap_or_else(null_value),
             };
@@ -277,16 +792,84 @@ fn select_branches(
     Ok(rows)
 }
 
-fn select_diffs(repo: &gix::Repository, selected_columns: &[String]) -> Result<Vec<Row>, String> {
+/// How a local branch relates to its configured upstream (`branch.<name>.merge`).
+struct UpstreamStatus {
+    name: String,
+    ahead: Option<usize>,
+    behind: Option<usize>,
+    merge_base: Option<gix::ObjectId>,
+}
+
+fn branch_upstream_status(
+    repo: &gix::Repository,
+    branch_name: &gix::refs::FullNameRef,
+    branch_id: gix::ObjectId,
+    signals: &Signals,
+) -> Option<UpstreamStatus> {
+    // `branch.<name>.remote = .` tracks another local branch, which has no
+    // remote-tracking ref, so the merge ref is the upstream itself.
+    let tracks_local_branch = repo
+        .branch_remote_name(branch_name.shorten(), gix::remote::Direction::Fetch)
+        .is_some_and(|remote| remote.as_bstr() == ".");
+    let upstream_name = if tracks_local_branch {
+        repo.branch_remote_ref_name(branch_name, gix::remote::Direction::Fetch)?
+            .ok()?
+    } else {
+        repo.branch_remote_tracking_ref_name(branch_name, gix::remote::Direction::Fetch)?
+            .ok()?
+    };
+    let mut status = UpstreamStatus {
+        name: upstream_name.shorten().to_string(),
+        ahead: None,
+        behind: None,
+        merge_base: None,
+    };
+
+    // The upstream may be configured but not fetched yet, or already deleted.
+    let Some(upstream_id) = repo
+        .find_reference(upstream_name.as_ref())
+        .ok()
+        .and_then(|mut reference| reference.peel_to_id().ok())
+        .map(|id| id.detach())
+    else {
+        return Some(status);
+    };
+
+    let count_exclusive = |tip: gix::ObjectId, hidden: gix::ObjectId| {
+        repo.rev_walk([tip])
+            .with_hidden([hidden])
+            .all()
+            .ok()
+            .map(|revwalk| revwalk.take_while(|_| !signals.interrupted()).count())
+    };
+    status.ahead = count_exclusive(branch_id, upstream_id);
+    status.behind = count_exclusive(upstream_id, branch_id);
+    status.merge_base = repo
+        .merge_base(branch_id, upstream_id)
+        .ok()
+        .map(|id| id.detach());
+    Some(status)
+}
+
+/// Whether `ancestor` is reachable from `descendant`.
+fn is_ancestor(repo: &gix::Repository, ancestor: gix::ObjectId, descendant: gix::ObjectId) -> bool {
+    ancestor == descendant
+        || repo
+            .merge_base(ancestor, descendant)
+            .is_ok_and(|merge_base| merge_base == ancestor)
+}
+
+fn select_diffs(
+    repo: &gix::Repository,
+    selected_columns: &[String],
+    revision: Option<&str>,
+    commit_filter: Option<&CommitFilter>,
+    signals: &Signals,
+) -> Result<Vec<Row>, String> {
     let mut repo = repo.clone();
     repo.object_cache_size_if_unset(4 * 1024 * 1024);
 
-    let revwalk = repo
-        .head_id()
-        .map_err(|err| err.to_string())?
-        .ancestors()
-        .all()
-        .map_err(|err| err.to_string())?;
+    let revwalk = revision_walk(&repo, revision)?;
     let repo_path = repo_workdir_path(&repo);
     let repo_name = repo_name_from_path(&repo_path);
 
@@ -300,14 +883,47 @@ fn select_diffs(repo: &gix::Repository, selected_columns: &[String]) -> Result<V
     let select_insertions_or_deletions = selected_columns
         .iter()
         .any(|column| column == "insertions" || column == "deletions");
+    let select_diff_stats = select_insertions_or_deletions
+        || selected_columns
+            .iter()
+            .any(|column| column == "files_changed");
 
     for commit_info in revwalk {
+        check_interrupted(signals)?;
         let commit_info = commit_info.map_err(|err| err.to_string())?;
+        if !matches_commit_id(commit_filter, &commit_info) {
+            continue;
+        }
         let commit = repo
             .find_object(commit_info.id)
             .map_err(|err| err.to_string())?
             .into_commit();
         let commit_ref = commit.decode().map_err(|err| err.to_string())?;
+        let datetime = commit_info
+            .commit_time
+            .unwrap_or_else(|| commit_ref.time().map(|time| time.seconds).unwrap_or(0));
+        // Skip, don't stop: an old commit can have newer ancestors.
+        if !matches_commit(commit_filter, datetime, &commit_ref) {
+            continue;
+        }
+
+        // The tree diff is the expensive part of a row, so run it at most once
+        // and let every stats column read from the same result.
+        let diff_stats = if select_diff_stats {
+            rewrite_cache.clear_resource_cache();
+            diff_cache.clear_resource_cache();
+            Some(commit_diff_stats(
+                &repo,
+                &commit,
+                commit_info.parent_ids().next(),
+                &mut rewrite_cache,
+                &mut diff_cache,
+                select_insertions_or_deletions,
+            )?)
+        } else {
+            None
+        };
+
         let mut values: Vec<Box<dyn Value>> = Vec::with_capacity(selected_columns.len());
 
         for column_name in selected_columns {
@@ -326,58 +942,16 @@ fn select_diffs(repo: &gix::Repository, selected_columns: &[String]) -> Result<V
                         .map(|author| author.email.to_string())
                         .unwrap_or_default(),
                 ),
-                "datetime" => Box::new(DateTimeValue {
-                    value: commit_info
-                        .commit_time
-                        .unwrap_or_else(|| commit_ref.time().map(|time| time.seconds).unwrap_or(0)),
-                }),
-                "insertions" | "deletions" | "files_changed" => {
-                    let current = commit.tree().map_err(|err| err.to_string())?;
-                    let previous = commit_info
-                        .parent_ids()
-                        .next()
-                        .map(|id| {
-                            repo.find_object(id)
-                                .map_err(|err| err.to_string())
-                                .and_then(|obj| {
-                                    obj.into_commit().tree().map_err(|err| err.to_string())
-                                })
-                        })
-                        .transpose()
-                        .map_err(|err| err.to_string())?
-                        .unwrap_or_else(|| repo.empty_tree());
-                    rewrite_cache.clear_resource_cache();
-                    diff_cache.clear_resource_cache();
-
-                    let (mut insertions, mut deletions, mut files_changed) = (0, 0, 0);
-                    previous
-                        .changes()
-                        .map_err(|err| err.to_string())?
-                        .for_each_to_obtain_tree_with_cache(
-                            &current,
-                            &mut rewrite_cache,
-                            |change| -> Result<_, Box<gix::object::blob::diff::init::Error>> {
-                                files_changed += usize::from(change.entry_mode().is_no_tree());
-                                if select_insertions_or_deletions {
-                                    if let Ok(mut platform) = change.diff(&mut diff_cache) {
-                                        if let Ok(Some(counts)) = platform.line_counts() {
-                                            deletions += counts.removals;
-                                            insertions += counts.insertions;
-                                        }
-                                    }
-                                }
-                                Ok(std::ops::ControlFlow::Continue(()))
-                            },
-                        )
-                        .map_err(|err| err.to_string())?;
-
-                    match column_name {
-                        "insertions" => int_value(insertions as i64),
-                        "deletions" => int_value(deletions as i64),
-                        "files_changed" => int_value(files_changed as i64),
-                        _ => null_value(),
-                    }
-                }
+                "datetime" => Box::new(DateTimeValue { value: datetime }),
+                "insertions" => diff_stats
+                    .as_ref()
+                    .map_or_else(null_value, |stats| int_value(stats.insertions as i64)),
+                "deletions" => diff_stats
+                    .as_ref()
+                    .map_or_else(null_value, |stats| int_value(stats.deletions as i64)),
+                "files_changed" => diff_stats
+                    .as_ref()
+                    .map_or_else(null_value, |stats| int_value(stats.files_changed as i64)),
                 _ => repo_metadata_value(column_name, &repo_path, &repo_name)
                     .unwrap_or_else(null_value),
             };
@@ -386,38 +960,971 @@ fn select_diffs(repo: &gix::Repository, selected_columns: &[String]) -> Result<V
 
         let row = Row { values };
         rows.push(row);
+        if commit_filter.is_some_and(|commit_filter| commit_filter.is_exhausted(rows.len())) {
+            break;
+        }
     }
 
     Ok(rows)
 }
 
+fn select_diff_files(
+    repo: &gix::Repository,
+    selected_columns: &[String],
+    revision: Option<&str>,
+    commit_filter: Option<&CommitFilter>,
+    signals: &Signals,
+) -> Result<Vec<Row>, String> {
+    let mut repo = repo.clone();
+    repo.object_cache_size_if_unset(4 * 1024 * 1024);
+
+    let revwalk = revision_walk(&repo, revision)?;
+    let repo_path = repo_workdir_path(&repo);
+    let repo_name = repo_name_from_path(&repo_path);
+
+    let mut rewrite_cache = repo
+        .diff_resource_cache(gix::diff::blob::pipeline::Mode::ToGit, Default::default())
+        .map_err(|err| err.to_string())?;
+
+    let mut diff_cache = rewrite_cache.clone();
+    let mut rows: Vec<Row> = vec![];
+
+    let select_line_counts = selected_columns
+        .iter()
+        .any(|column| matches!(column.as_str(), "insertions" | "deletions" | "is_binary"));
+
+    for commit_info in revwalk {
+        check_interrupted(signals)?;
+        let commit_info = commit_info.map_err(|err| err.to_string())?;
+        if !matches_commit_id(commit_filter, &commit_info) {
+            continue;
+        }
+        let commit = repo
+            .find_object(commit_info.id)
+            .map_err(|err| err.to_string())?
+            .into_commit();
+        let current = commit.tree().map_err(|err| err.to_string())?;
+        let previous = first_parent_tree(&repo, commit_info.parent_ids().next())?;
+        rewrite_cache.clear_resource_cache();
+        diff_cache.clear_resource_cache();
+
+        previous
+            .changes()
+            .map_err(|err| err.to_string())?
+            .for_each_to_obtain_tree_with_cache(
+                &current,
+                &mut rewrite_cache,
+                |change| -> Result<_, Box<gix::object::blob::diff::init::Error>> {
+                    if !change.entry_mode().is_no_tree() {
+                        return Ok(std::ops::ControlFlow::Continue(()));
+                    }
+
+                    let line_counts = if select_line_counts {
+                        change
+                            .diff(&mut diff_cache)
+                            .ok()
+                            .and_then(|mut platform| platform.line_counts().ok())
+                    } else {
+                        None
+                    };
+
+                    let mut values: Vec<Box<dyn Value>> =
+                        Vec::with_capacity(selected_columns.len());
+                    for column_name in selected_columns {
+                        let column_name = column_name.as_str();
+                        let value = match column_name {
+                            "commit_id" => text_value(commit_info.id.to_string()),
+                            "path" => text_value(change.location().to_string()),
+                            "old_path" => {
+                                diff_change_old_path(&change).map_or_else(null_value, text_value)
+                            }
+                            "change_type" => text_value(diff_change_type(&change)),
+                            "insertions" => match &line_counts {
+                                Some(Some(counts)) => int_value(counts.insertions as i64),
+                                _ => int_value(0),
+                            },
+                            "deletions" => match &line_counts {
+                                Some(Some(counts)) => int_value(counts.removals as i64),
+                                _ => int_value(0),
+                            },
+                            "is_binary" => bool_value(matches!(line_counts, Some(None))),
+                            "old_mode" => diff_change_old_mode(&change)
+                                .map_or_else(null_value, entry_mode_value),
+                            "new_mode" => diff_change_new_mode(&change)
+                                .map_or_else(null_value, entry_mode_value),
+                            _ => repo_metadata_value(column_name, &repo_path, &repo_name)
+                                .unwrap_or_else(null_value),
+                        };
+                        values.push(value);
+                    }
+
+                    rows.push(Row { values });
+                    Ok(std::ops::ControlFlow::Continue(()))
+                },
+            )
+            .map_err(|err| err.to_string())?;
+        if commit_filter.is_some_and(|commit_filter| commit_filter.is_exhausted(rows.len())) {
+            break;
+        }
+    }
+
+    Ok(rows)
+}
+
+/// Line and file totals for a single commit compared to its first parent.
+struct DiffStats {
+    insertions: u64,
+    deletions: u64,
+    files_changed: u64,
+}
+
+fn commit_diff_stats<'repo>(
+    repo: &'repo gix::Repository,
+    commit: &gix::Commit<'_>,
+    first_parent: Option<gix::Id<'repo>>,
+    rewrite_cache: &mut gix::diff::blob::Platform,
+    diff_cache: &mut gix::diff::blob::Platform,
+    count_lines: bool,
+) -> Result<DiffStats, String> {
+    let current = commit.tree().map_err(|err| err.to_string())?;
+    let previous = first_parent_tree(repo, first_parent)?;
+
+    let mut stats = DiffStats {
+        insertions: 0,
+        deletions: 0,
+        files_changed: 0,
+    };
+    previous
+        .changes()
+        .map_err(|err| err.to_string())?
+        .for_each_to_obtain_tree_with_cache(
+            &current,
+            rewrite_cache,
+            |change| -> Result<_, Box<gix::object::blob::diff::init::Error>> {
+                stats.files_changed += u64::from(change.entry_mode().is_no_tree());
+                if count_lines
+                    && let Ok(mut platform) = change.diff(diff_cache)
+                    && let Ok(Some(counts)) = platform.line_counts()
+                {
+                    stats.deletions += u64::from(counts.removals);
+                    stats.insertions += u64::from(counts.insertions);
+                }
+                Ok(std::ops::ControlFlow::Continue(()))
+            },
+        )
+        .map_err(|err| err.to_string())?;
+
+    Ok(stats)
+}
+
+/// Tree of a commit's `first_parent`, or the empty tree for root commits.
+fn first_parent_tree<'repo>(
+    repo: &'repo gix::Repository,
+    first_parent: Option<gix::Id<'repo>>,
+) -> Result<gix::Tree<'repo>, String> {
+    first_parent
+        .map(|id| {
+            repo.find_object(id)
+                .map_err(|err| err.to_string())
+                .and_then(|obj| obj.into_commit().tree().map_err(|err| err.to_string()))
+        })
+        .transpose()
+        .map(|tree| tree.unwrap_or_else(|| repo.empty_tree()))
+}
+
+fn diff_change_type(change: &gix::object::tree::diff::Change<'_, '_, '_>) -> &'static str {
+    use gix::object::tree::diff::Change;
+    match change {
+        Change::Addition { .. } => "added",
+        Change::Deletion { .. } => "deleted",
+        Change::Modification { .. } => "modified",
+        Change::Rewrite { copy: true, .. } => "copied",
+        Change::Rewrite { .. } => "renamed",
+    }
+}
+
+fn diff_change_old_path(change: &gix::object::tree::diff::Change<'_, '_, '_>) -> Option<String> {
+    use gix::object::tree::diff::Change;
+    match change {
+        Change::Addition { .. } => None,
+        Change::Deletion { location, .. } | Change::Modification { location, .. } => {
+            Some(location.to_string())
+        }
+        Change::Rewrite {
+            source_location, ..
+        } => Some(source_location.to_string()),
+    }
+}
+
+fn diff_change_old_mode(
+    change: &gix::object::tree::diff::Change<'_, '_, '_>,
+) -> Option<gix::object::tree::EntryMode> {
+    use gix::object::tree::diff::Change;
+    match change {
+        Change::Addition { .. } => None,
+        Change::Deletion { entry_mode, .. } => Some(*entry_mode),
+        Change::Modification {
+            previous_entry_mode,
+            ..
+        } => Some(*previous_entry_mode),
+        Change::Rewrite {
+            source_entry_mode, ..
+        } => Some(*source_entry_mode),
+    }
+}
+
+fn diff_change_new_mode(
+    change: &gix::object::tree::diff::Change<'_, '_, '_>,
+) -> Option<gix::object::tree::EntryMode> {
+    use gix::object::tree::diff::Change;
+    match change {
+        Change::Deletion { .. } => None,
+        _ => Some(change.entry_mode()),
+    }
+}
+
+fn entry_mode_value(mode: gix::object::tree::EntryMode) -> Box<dyn Value> {
+    text_value(mode.kind().as_octal_str())
+}
+
 fn select_tags(repo: &gix::Repository, selected_columns: &[String]) -> Result<Vec<Row>, String> {
     let platform = repo.references().map_err(|err| err.to_string())?;
     let tag_names = platform.tags().map_err(|err| err.to_string())?;
     let repo_path = repo_workdir_path(repo);
     let repo_name = repo_name_from_path(&repo_path);
+    let select_tag_details = selected_columns
+        .iter()
+        .any(|column| !matches!(column.as_str(), "name" | "full_name" | "repo" | "repo_name"));
     let mut rows: Vec<Row> = vec![];
     for tag_ref in tag_names.flatten() {
+        let details = if select_tag_details {
+            tag_ref
+                .try_id()
+                .map(|id| tag_details(repo, id.detach()))
+                .transpose()?
+        } else {
+            None
+        };
+        let mut values: Vec<Box<dyn Value>> = Vec::with_capacity(selected_columns.len());
+
+        for column_name in selected_columns {
+            let column_name = column_name.as_str();
+            let value =
+                match column_name {
+                    "name" => text_value(
+                        tag_ref
+                            .name()
+                            .category_and_short_name()
+                            .map_or_else(String::default, |(_, short_name)| short_name.to_string()),
+                    ),
+                    "full_name" => text_value(tag_ref.name().as_bstr().to_string()),
+                    "target_id" => details
+                        .as_ref()
+                        .map_or_else(null_value, |details| text_value(details.target_id)),
+                    "target_type" => details
+                        .as_ref()
+                        .map_or_else(null_value, |details| text_value(details.target_kind)),
+                    "is_annotated" => bool_value(details.as_ref().is_some_and(|details| {
+                        details.tagger.is_some() || details.message.is_some()
+                    })),
+                    "tagger_name" => details
+                        .as_ref()
+                        .and_then(|details| details.tagger.as_ref())
+                        .map_or_else(null_value, |(name, _, _)| text_value(name)),
+                    "tagger_email" => details
+                        .as_ref()
+                        .and_then(|details| details.tagger.as_ref())
+                        .map_or_else(null_value, |(_, email, _)| text_value(email)),
+                    "tagger_datetime" => details
+                        .as_ref()
+                        .and_then(|details| details.tagger.as_ref())
+                        .map_or_else(null_value, |(_, _, seconds)| {
+                            Box::new(DateTimeValue { value: *seconds })
+                        }),
+                    "message" => details
+                        .as_ref()
+                        .and_then(|details| details.message.as_ref())
+                        .map_or_else(null_value, text_value),
+                    "title" => details
+                        .as_ref()
+                        .and_then(|details| details.message.as_ref())
+                        .map_or_else(null_value, |message| {
+                            text_value(message.lines().next().unwrap_or_default().trim())
+                        }),
+                    "commit_datetime" => details
+                        .as_ref()
+                        .and_then(|details| details.commit_time)
+                        .map_or_else(null_value, |seconds| {
+                            Box::new(DateTimeValue { value: seconds })
+                        }),
+                    _ => repo_metadata_value(column_name, &repo_path, &repo_name)
+                        .unwrap_or_else(null_value),
+                };
+            values.push(value);
+        }
+
+        let row = Row { values };
+        rows.push(row);
+    }
+
+    Ok(rows)
+}
+
+/// What a tag reference points at. Lightweight tags have no tagger or message.
+struct TagDetails {
+    target_id: gix::ObjectId,
+    target_kind: gix::object::Kind,
+    tagger: Option<(String, String, i64)>,
+    message: Option<String>,
+    commit_time: Option<i64>,
+}
+
+fn tag_details(repo: &gix::Repository, id: gix::ObjectId) -> Result<TagDetails, String> {
+    let object = repo.find_object(id).map_err(|err| err.to_string())?;
+    let commit_time = object
+        .clone()
+        .peel_to_commit()
+        .ok()
+        .and_then(|commit| commit.time().ok())
+        .map(|time| time.seconds);
+
+    if object.kind != gix::object::Kind::Tag {
+        return Ok(TagDetails {
+            target_id: id,
+            target_kind: object.kind,
+            tagger: None,
+            message: None,
+            commit_time,
+        });
+    }
+
+    let tag = object.into_tag();
+    let tag = tag.decode().map_err(|err| err.to_string())?;
+    let tagger = tag.tagger().map_err(|err| err.to_string())?.map(|tagger| {
+        (
+            tagger.name.to_string(),
+            tagger.email.to_string(),
+            tagger.seconds(),
+        )
+    });
+
+    Ok(TagDetails {
+        target_id: tag.target(),
+        target_kind: tag.target_kind,
+        tagger,
+        message: Some(tag.message.to_string()),
+        commit_time,
+    })
+}
+
+fn select_blame(
+    repo: &gix::Repository,
+    selected_columns: &[String],
+    revision: Option<&str>,
+    path: Option<&str>,
+) -> Result<Vec<Row>, String> {
+    let path =
+        path.ok_or_else(|| "The blame table requires a file, pass it with --path".to_string())?;
+    let repo_path = repo_workdir_path(repo);
+    let repo_name = repo_name_from_path(&repo_path);
+    let suspect = resolve_commit_id(repo, revision)?;
+
+    let outcome = repo
+        .blame_file(path.into(), suspect, Default::default())
+        .map_err(|err| format!("Unable to blame '{path}': {err}"))?;
+
+    // Hunks from the same commit are common, so only decode each commit once.
+    let mut authors: HashMap<gix::ObjectId, (String, String, i64)> = HashMap::new();
+    let mut rows: Vec<Row> = vec![];
+
+    for (entry, lines) in outcome.entries_with_lines() {
+        let (author_name, author_email, author_time) = match authors.get(&entry.commit_id) {
+            Some(author) => author.clone(),
+            None => {
+                let commit = repo
+                    .find_commit(entry.commit_id)
+                    .map_err(|err| err.to_string())?;
+                let author = commit.author().map_err(|err| err.to_string())?;
+                let author = (
+                    author.name.to_string(),
+                    author.email.to_string(),
+                    author.seconds(),
+                );
+                authors.insert(entry.commit_id, author.clone());
+                author
+            }
+        };
+
+        for (offset, line) in lines.iter().enumerate() {
+            let mut values: Vec<Box<dyn Value>> = Vec::with_capacity(selected_columns.len());
+
+            for column_name in selected_columns {
+                let column_name = column_name.as_str();
+                let value = match column_name {
+                    "path" => text_value(path),
+                    "line_number" => {
+                        int_value(i64::from(entry.start_in_blamed_file) + offset as i64 + 1)
+                    }
+                    "original_line_number" => {
+                        int_value(i64::from(entry.start_in_source_file) + offset as i64 + 1)
+                    }
+                    "commit_id" => text_value(entry.commit_id),
+                    "author_name" => text_value(&author_name),
+                    "author_email" => text_value(&author_email),
+                    "datetime" => Box::new(DateTimeValue { value: author_time }),
+                    "content" => text_value(
+                        line.to_str_lossy()
+                            .trim_end_matches('\n')
+                            .trim_end_matches('\r'),
+                    ),
+                    _ => repo_metadata_value(column_name, &repo_path, &repo_name)
+                        .unwrap_or_else(null_value),
+                };
+                values.push(value);
+            }
+
+            rows.push(Row { values });
+        }
+    }
+
+    Ok(rows)
+}
+
+fn select_files(
+    repo: &gix::Repository,
+    selected_columns: &[String],
+    revision: Option<&str>,
+) -> Result<Vec<Row>, String> {
+    let repo_path = repo_workdir_path(repo);
+    let repo_name = repo_name_from_path(&repo_path);
+    let commit_id = resolve_commit_id(repo, revision)?;
+    let tree = repo
+        .find_commit(commit_id)
+        .map_err(|err| err.to_string())?
+        .tree()
+        .map_err(|err| err.to_string())?;
+
+    let mut recorder = gix::traverse::tree::Recorder::default();
+    tree.traverse()
+        .depthfirst(&mut recorder)
+        .map_err(|err| err.to_string())?;
+
+    let mut rows: Vec<Row> = vec![];
+    for entry in recorder.records {
+        if entry.mode.is_tree() {
+            continue;
+        }
+
+        let path = entry.filepath.to_str_lossy();
+        let name = path.rsplit('/').next().unwrap_or_default();
         let mut values: Vec<Box<dyn Value>> = Vec::with_capacity(selected_columns.len());
 
         for column_name in selected_columns {
             let column_name = column_name.as_str();
             let value = match column_name {
-                "name" => text_value(
-                    tag_ref
-                        .name()
-                        .category_and_short_name()
-                        .map_or_else(String::default, |(_, short_name)| short_name.to_string()),
+                "path" => text_value(&path),
+                "name" => text_value(name),
+                "extension" => Path::new(name)
+                    .extension()
+                    .map_or_else(null_value, |extension| {
+                        text_value(extension.to_string_lossy())
+                    }),
+                "mode" => entry_mode_value(entry.mode),
+                "size" => {
+                    if entry.mode.is_commit() {
+                        null_value()
+                    } else {
+                        repo.find_header(entry.oid)
+                            .map_or_else(|_| null_value(), |header| int_value(header.size() as i64))
+                    }
+                }
+                "blob_id" => text_value(entry.oid),
+                "is_executable" => bool_value(entry.mode.is_executable()),
+                "is_symlink" => bool_value(entry.mode.is_link()),
+                "is_submodule" => bool_value(entry.mode.is_commit()),
+                "depth" => int_value(path.matches('/').count() as i64),
+                _ => repo_metadata_value(column_name, &repo_path, &repo_name)
+                    .unwrap_or_else(null_value),
+            };
+            values.push(value);
+        }
+
+        rows.push(Row { values });
+    }
+
+    Ok(rows)
+}
+
+/// Combined index and worktree state of a single path, like a line of `git status --short`.
+#[derive(Default)]
+struct PathStatus {
+    index_status: Option<&'static str>,
+    worktree_status: Option<&'static str>,
+    is_untracked: bool,
+    is_ignored: bool,
+    is_conflicted: bool,
+    rename_from: Option<String>,
+}
+
+fn select_status(repo: &gix::Repository, selected_columns: &[String]) -> Result<Vec<Row>, String> {
+    let mut rows: Vec<Row> = vec![];
+    if repo.workdir().is_none() {
+        return Ok(rows);
+    }
+
+    let repo_path = repo_workdir_path(repo);
+    let repo_name = repo_name_from_path(&repo_path);
+    let status = repo
+        .status(gix::progress::Discard)
+        .map_err(|err| err.to_string())?
+        .dirwalk_options(|options| {
+            options.emit_ignored(Some(gix::dir::walk::EmissionMode::CollapseDirectory))
+        })
+        .into_iter(None)
+        .map_err(|err| err.to_string())?;
+
+    // Index and worktree changes arrive interleaved and unordered, so merge
+    // them per path and emit the paths sorted.
+    let mut paths: BTreeMap<String, PathStatus> = BTreeMap::new();
+    for item in status {
+        let item = item.map_err(|err| err.to_string())?;
+        match item {
+            gix::status::Item::TreeIndex(change) => {
+                use gix::diff::index::ChangeRef;
+                let path_status = paths.entry(change.location().to_string()).or_default();
+                path_status.index_status = Some(match &change {
+                    ChangeRef::Addition { .. } => "added",
+                    ChangeRef::Deletion { .. } => "deleted",
+                    ChangeRef::Modification { .. } => "modified",
+                    ChangeRef::Rewrite { copy: true, .. } => "copied",
+                    ChangeRef::Rewrite { .. } => "renamed",
+                });
+                if let ChangeRef::Rewrite {
+                    source_location, ..
+                } = &change
+                {
+                    path_status.rename_from = Some(source_location.to_string());
+                }
+            }
+            gix::status::Item::IndexWorktree(item) => {
+                use gix::status::index_worktree::Item;
+                use gix::status::index_worktree::iter::Summary;
+
+                if let Item::DirectoryContents { entry, .. } = &item
+                    && matches!(entry.status, gix::dir::entry::Status::Ignored(_))
+                {
+                    let path_status = paths.entry(item.rela_path().to_string()).or_default();
+                    path_status.is_ignored = true;
+                    path_status.worktree_status = Some("ignored");
+                    continue;
+                }
+
+                let Some(summary) = item.summary() else {
+                    continue;
+                };
+                let path_status = paths.entry(item.rela_path().to_string()).or_default();
+                path_status.worktree_status = Some(match summary {
+                    Summary::Added => {
+                        path_status.is_untracked = true;
+                        "untracked"
+                    }
+                    Summary::Removed => "deleted",
+                    Summary::Modified => "modified",
+                    Summary::TypeChange => "type_changed",
+                    Summary::Renamed => "renamed",
+                    Summary::Copied => "copied",
+                    Summary::IntentToAdd => "intent_to_add",
+                    Summary::Conflict => {
+                        path_status.is_conflicted = true;
+                        "conflicted"
+                    }
+                });
+                if let Item::Rewrite { source, .. } = &item {
+                    path_status.rename_from = Some(source.rela_path().to_string());
+                }
+            }
+        }
+    }
+
+    for (path, path_status) in paths {
+        let mut values: Vec<Box<dyn Value>> = Vec::with_capacity(selected_columns.len());
+
+        for column_name in selected_columns {
+            let column_name = column_name.as_str();
+            let value = match column_name {
+                "path" => text_value(&path),
+                "index_status" => path_status.index_status.map_or_else(null_value, text_value),
+                "worktree_status" => path_status
+                    .worktree_status
+                    .map_or_else(null_value, text_value),
+                "is_untracked" => bool_value(path_status.is_untracked),
+                "is_ignored" => bool_value(path_status.is_ignored),
+                "is_conflicted" => bool_value(path_status.is_conflicted),
+                "rename_from" => path_status
+                    .rename_from
+                    .as_ref()
+                    .map_or_else(null_value, text_value),
+                _ => repo_metadata_value(column_name, &repo_path, &repo_name)
+                    .unwrap_or_else(null_value),
+            };
+            values.push(value);
+        }
+
+        rows.push(Row { values });
+    }
+
+    Ok(rows)
+}
+
+fn select_remotes(repo: &gix::Repository, selected_columns: &[String]) -> Result<Vec<Row>, String> {
+    use gix::remote::Direction;
+
+    let repo_path = repo_workdir_path(repo);
+    let repo_name = repo_name_from_path(&repo_path);
+    let default_name = repo.remote_default_name(Direction::Fetch);
+    let mut rows: Vec<Row> = vec![];
+
+    for name in repo.remote_names() {
+        let remote = repo
+            .find_remote(name.as_ref())
+            .map_err(|err| err.to_string())?;
+        // Refspecs can't contain spaces, so a space-separated list stays
+        // unambiguous.
+        let refspecs = |direction| {
+            remote
+                .refspecs(direction)
+                .iter()
+                .map(|refspec| refspec.to_ref().to_bstring().to_string())
+                .collect::<Vec<_>>()
+                .join(" ")
+        };
+        let url = |direction| {
+            remote
+                .url(direction)
+                .map_or_else(null_value, |url| text_value(url.to_bstring()))
+        };
+
+        let mut values: Vec<Box<dyn Value>> = Vec::with_capacity(selected_columns.len());
+        for column_name in selected_columns {
+            let column_name = column_name.as_str();
+            let value = match column_name {
+                "name" => text_value(name.as_ref()),
+                "fetch_url" => url(Direction::Fetch),
+                "push_url" => url(Direction::Push),
+                "fetch_refspecs" => text_value(refspecs(Direction::Fetch)),
+                "push_refspecs" => text_value(refspecs(Direction::Push)),
+                "tag_mode" => text_value(match remote.fetch_tags() {
+                    gix::remote::fetch::Tags::All => "all",
+                    gix::remote::fetch::Tags::Included => "auto",
+                    gix::remote::fetch::Tags::None => "none",
+                }),
+                "is_default" => bool_value(default_name.as_ref() == Some(&name)),
+                _ => repo_metadata_value(column_name, &repo_path, &repo_name)
+                    .unwrap_or_else(null_value),
+            };
+            values.push(value);
+        }
+
+        rows.push(Row { values });
+    }
+
+    Ok(rows)
+}
+
+fn select_stashes(repo: &gix::Repository, selected_columns: &[String]) -> Result<Vec<Row>, String> {
+    let mut rows: Vec<Row> = vec![];
+    let Some(stash) = repo
+        .try_find_reference("refs/stash")
+        .map_err(|err| err.to_string())?
+    else {
+        return Ok(rows);
+    };
+
+    let repo_path = repo_workdir_path(repo);
+    let repo_name = repo_name_from_path(&repo_path);
+    let mut log_iter = stash.log_iter();
+    let mut entries = match log_iter.all().map_err(|err| err.to_string())? {
+        Some(lines) => lines
+            .map(|line| line.map(|line| line.to_owned()))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|err| err.to_string())?,
+        None => vec![],
+    };
+    // The reflog is oldest first, while stash@{0} is the latest stash.
+    entries.reverse();
+
+    let select_files_changed = selected_columns
+        .iter()
+        .any(|column| column == "files_changed");
+    let mut rewrite_cache = repo
+        .diff_resource_cache(gix::diff::blob::pipeline::Mode::ToGit, Default::default())
+        .map_err(|err| err.to_string())?;
+    let mut diff_cache = rewrite_cache.clone();
+
+    for (index, entry) in entries.iter().enumerate() {
+        let commit = repo
+            .find_commit(entry.new_oid)
+            .map_err(|err| err.to_string())?;
+        let message = entry.message.to_string();
+        // The stash commit records the worktree against the commit that was
+        // checked out, which is its first parent.
+        let files_changed = if select_files_changed {
+            Some(
+                commit_diff_stats(
+                    repo,
+                    &commit,
+                    commit.parent_ids().next(),
+                    &mut rewrite_cache,
+                    &mut diff_cache,
+                    false,
+                )?
+                .files_changed,
+            )
+        } else {
+            None
+        };
+
+        let mut values: Vec<Box<dyn Value>> = Vec::with_capacity(selected_columns.len());
+        for column_name in selected_columns {
+            let column_name = column_name.as_str();
+            let value = match column_name {
+                "index" => int_value(index as i64),
+                "commit_id" => text_value(entry.new_oid),
+                "message" => text_value(&message),
+                "branch" => stash_branch(&message).map_or_else(null_value, text_value),
+                "datetime" => Box::new(DateTimeValue {
+                    value: entry.signature.time.seconds,
+                }),
+                "author_name" => text_value(
+                    commit
+                        .author()
+                        .map(|author| author.name.to_string())
+                        .unwrap_or_default(),
                 ),
+                "files_changed" => {
+                    files_changed.map_or_else(null_value, |files| int_value(files as i64))
+                }
                 _ => repo_metadata_value(column_name, &repo_path, &repo_name)
                     .unwrap_or_else(null_value),
             };
             values.push(value);
         }
 
-        let row = Row { values };
-        rows.push(row);
+        rows.push(Row { values });
+    }
+
+    Ok(rows)
+}
+
+/// Branch a stash was made on, from the `WIP on <branch>: ...` or
+/// `On <branch>: ...` message git writes. Detached stashes have none.
+fn stash_branch(message: &str) -> Option<&str> {
+    let rest = message
+        .strip_prefix("WIP on ")
+        .or_else(|| message.strip_prefix("On "))?;
+    let (branch, _) = rest.split_once(':')?;
+    (branch != "(no branch)").then_some(branch)
+}
+
+/// Reflog entries of `HEAD`, followed by those of every other ref that keeps
+/// a log when `all_refs` is set.
+fn select_reflog(
+    repo: &gix::Repository,
+    selected_columns: &[String],
+    all_refs: bool,
+) -> Result<Vec<Row>, String> {
+    let mut references = vec![];
+    if let Some(head) = repo
+        .try_find_reference("HEAD")
+        .map_err(|err| err.to_string())?
+    {
+        references.push(head);
+    }
+    if all_refs {
+        let platform = repo.references().map_err(|err| err.to_string())?;
+        for reference in platform.all().map_err(|err| err.to_string())? {
+            references.push(reference.map_err(|err| err.to_string())?);
+        }
+    }
+
+    let repo_path = repo_workdir_path(repo);
+    let repo_name = repo_name_from_path(&repo_path);
+    let mut rows: Vec<Row> = vec![];
+    for reference in references {
+        let ref_name = reference.name().as_bstr().to_string();
+        let mut log_iter = reference.log_iter();
+        let Some(lines) = log_iter.all().map_err(|err| err.to_string())? else {
+            continue;
+        };
+        let mut entries = lines
+            .map(|line| line.map(|line| line.to_owned()))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|err| err.to_string())?;
+        // The reflog is oldest first, while ref@{0} is the latest entry.
+        entries.reverse();
+
+        for (index, entry) in entries.iter().enumerate() {
+            let message = entry.message.to_string();
+            let mut values: Vec<Box<dyn Value>> = Vec::with_capacity(selected_columns.len());
+            for column_name in selected_columns {
+                let column_name = column_name.as_str();
+                let value = match column_name {
+                    "ref_name" => text_value(&ref_name),
+                    "index" => int_value(index as i64),
+                    "old_id" if entry.previous_oid.is_null() => null_value(),
+                    "old_id" => text_value(entry.previous_oid),
+                    "new_id" if entry.new_oid.is_null() => null_value(),
+                    "new_id" => text_value(entry.new_oid),
+                    "committer_name" => text_value(&entry.signature.name),
+                    "committer_email" => text_value(&entry.signature.email),
+                    "datetime" => Box::new(DateTimeValue {
+                        value: entry.signature.time.seconds,
+                    }),
+                    "message" => text_value(&message),
+                    "operation" => reflog_operation(&message).map_or_else(null_value, text_value),
+                    _ => repo_metadata_value(column_name, &repo_path, &repo_name)
+                        .unwrap_or_else(null_value),
+                };
+                values.push(value);
+            }
+
+            rows.push(Row { values });
+        }
     }
 
     Ok(rows)
 }
+
+/// Commands whose reflog messages start with their name.
+const REFLOG_OPERATIONS: [&str; 11] = [
+    "commit",
+    "checkout",
+    "rebase",
+    "reset",
+    "merge",
+    "pull",
+    "cherry-pick",
+    "revert",
+    "branch",
+    "clone",
+    "fetch",
+];
+
+/// Command that wrote a reflog entry, from the `<operation>: ...`,
+/// `<operation> (<step>): ...` or `<operation> <args>: ...` message git
+/// writes, e.g. `commit (amend)` is a `commit` and `rebase -i (finish)` is a
+/// `rebase`. Messages set with `update-ref -m` have none.
+fn reflog_operation(message: &str) -> Option<&'static str> {
+    let (head, _) = message.split_once(':')?;
+    REFLOG_OPERATIONS.into_iter().find(|operation| {
+        head.strip_prefix(operation)
+            .is_some_and(|rest| rest.is_empty() || rest.starts_with(' '))
+    })
+}
+
+/// One checkout of a repository: the main worktree or a linked one.
+struct WorktreeInfo {
+    id: Option<String>,
+    path: String,
+    head_commit: Option<String>,
+    branch: Option<String>,
+    is_main: bool,
+    is_locked: bool,
+    lock_reason: Option<String>,
+    is_prunable: bool,
+}
+
+fn select_worktrees(
+    repo: &gix::Repository,
+    selected_columns: &[String],
+) -> Result<Vec<Row>, String> {
+    // Linked worktrees share the main repository's worktree list, so every
+    // checkout of a repository reports the same rows.
+    let main_repo = repo.main_repo().map_err(|err| err.to_string())?;
+    let mut worktrees = vec![];
+    if let Some(workdir) = main_repo.workdir() {
+        let (head_commit, branch) = worktree_head(&main_repo);
+        worktrees.push(WorktreeInfo {
+            id: None,
+            path: workdir.to_string_lossy().into_owned(),
+            head_commit,
+            branch,
+            is_main: true,
+            is_locked: false,
+            lock_reason: None,
+            is_prunable: false,
+        });
+    }
+
+    for proxy in main_repo.worktrees().map_err(|err| err.to_string())? {
+        let id = proxy.id().to_string();
+        let base = proxy.base().ok();
+        let is_locked = proxy.is_locked();
+        let lock_reason = proxy
+            .lock_reason()
+            .map(|reason| reason.to_string())
+            .filter(|reason| !reason.is_empty());
+        let linked_repo = proxy
+            .into_repo_with_possibly_inaccessible_worktree()
+            .map_err(|err| err.to_string())?;
+        let (head_commit, branch) = worktree_head(&linked_repo);
+        worktrees.push(WorktreeInfo {
+            id: Some(id),
+            // git prunes a worktree whose checkout is gone unless it is locked.
+            is_prunable: !is_locked && !base.as_ref().is_some_and(|base| base.is_dir()),
+            path: base
+                .map(|base| base.to_string_lossy().into_owned())
+                .unwrap_or_default(),
+            head_commit,
+            branch,
+            is_main: false,
+            is_locked,
+            lock_reason,
+        });
+    }
+
+    let repo_path = repo_workdir_path(repo);
+    let repo_name = repo_name_from_path(&repo_path);
+    let mut rows: Vec<Row> = Vec::with_capacity(worktrees.len());
+    for worktree in worktrees {
+        let mut values: Vec<Box<dyn Value>> = Vec::with_capacity(selected_columns.len());
+        for column_name in selected_columns {
+            let column_name = column_name.as_str();
+            let value = match column_name {
+                "id" => worktree.id.as_ref().map_or_else(null_value, text_value),
+                "path" => text_value(&worktree.path),
+                "head_commit" => worktree
+                    .head_commit
+                    .as_ref()
+                    .map_or_else(null_value, text_value),
+                "branch" => worktree.branch.as_ref().map_or_else(null_value, text_value),
+                "is_main" => bool_value(worktree.is_main),
+                "is_locked" => bool_value(worktree.is_locked),
+                "lock_reason" => worktree
+                    .lock_reason
+                    .as_ref()
+                    .map_or_else(null_value, text_value),
+                "is_prunable" => bool_value(worktree.is_prunable),
+                _ => repo_metadata_value(column_name, &repo_path, &repo_name)
+                    .unwrap_or_else(null_value),
+            };
+            values.push(value);
+        }
+
+        rows.push(Row { values });
+    }
+
+    Ok(rows)
+}
+
+/// Commit checked out in a worktree and its branch, which is `None` when
+/// `HEAD` is detached.
+fn worktree_head(repo: &gix::Repository) -> (Option<String>, Option<String>) {
+    let head_commit = repo.head_id().ok().map(|id| id.to_string());
+    let branch = repo
+        .head_name()
+        .ok()
+        .flatten()
+        .map(|name| name.shorten().to_string());
+    (head_commit, branch)
+}
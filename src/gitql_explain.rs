@@ -0,0 +1,433 @@
+use crate::gitql_pushdown::CommitFilter;
+use crate::gitql_schema::expensive_column_reason;
+use chrono::{TimeZone, Utc};
+use gitql_ast::expression::{
+    ArithmeticExpr, ArrayExpr, BetweenExpr, BooleanExpr, CallExpr, CastExpr, ColumnExpr,
+    ComparisonExpr, ContainedByExpr, ContainsExpr, Expr, ExprKind, GlobExpr, GlobalVariableExpr,
+    InExpr, IsNullExpr, LikeExpr, LogicalExpr, Number, NumberExpr, RegexExpr, StringExpr,
+    SymbolExpr, UnaryExpr,
+};
+use gitql_ast::operator::{
+    ArithmeticOperator, BinaryLogicalOperator, ComparisonOperator, PrefixUnaryOperator,
+};
+use gitql_ast::query::{Query, SelectQuery};
+use gitql_ast::statement::Statement;
+use nu_protocol::{Record, Span, Value};
+
+/// Order in which the engine runs the statements of a select query.
+const STATEMENT_ORDER: [&str; 11] = [
+    "select",
+    "where",
+    "group",
+    "aggregation",
+    "having",
+    "window_functions",
+    "qualify",
+    "order",
+    "offset",
+    "limit",
+    "into",
+];
+
+/// Describe what the engine will do for each parsed query, without reading
+/// any repository.
+pub fn explain_queries(queries: &[Query], span: Span) -> Value {
+    let plans = queries
+        .iter()
+        .map(|query| explain_query(query, span))
+        .collect();
+    Value::list(plans, span)
+}
+
+fn explain_query(query: &Query, span: Span) -> Value {
+    let mut record = Record::new();
+    match query {
+        Query::Select(select) => return explain_select(select, span),
+        Query::GlobalVariableDecl(declaration) => {
+            record.insert("query", Value::string("set", span));
+            record.insert("variable", Value::string(&declaration.name, span));
+            record.insert("value", expr_to_value(declaration.value.as_ref(), span));
+        }
+        Query::Do(do_query) => {
+            record.insert("query", Value::string("do", span));
+            let exprs = do_query
+                .exprs
+                .iter()
+                .map(|expr| expr_to_value(expr.as_ref(), span))
+                .collect();
+            record.insert("expressions", Value::list(exprs, span));
+        }
+        Query::DescribeTable(describe) => {
+            record.insert("query", Value::string("describe", span));
+            record.insert("table", Value::string(&describe.table_name, span));
+        }
+        Query::ShowTables => {
+            record.insert("query", Value::string("show tables", span));
+        }
+    }
+    Value::record(record, span)
+}
+
+fn explain_select(select: &SelectQuery, span: Span) -> Value {
+    let mut tables = vec![];
+    let mut selected_columns = vec![];
+    let mut columns_read = vec![];
+    let mut expensive_columns = vec![];
+    let mut filters = vec![];
+    let mut statements = vec![];
+
+    for name in STATEMENT_ORDER {
+        let Some(statement) = select.statements.get(name) else {
+            continue;
+        };
+
+        let mut step = Record::new();
+        step.insert("statement", Value::string(name, span));
+        match statement {
+            Statement::Select(statement) => {
+                for selection in &statement.table_selections {
+                    tables.push(Value::string(&selection.table_name, span));
+                    for column in &selection.columns_names {
+                        let mut read = Record::new();
+                        read.insert("table", Value::string(&selection.table_name, span));
+                        read.insert("column", Value::string(column, span));
+                        columns_read.push(Value::record(read, span));
+
+                        if let Some(reason) = expensive_column_reason(&selection.table_name, column)
+                        {
+                            let mut expensive = Record::new();
+                            expensive.insert("table", Value::string(&selection.table_name, span));
+                            expensive.insert("column", Value::string(column, span));
+                            expensive.insert("reason", Value::string(reason, span));
+                            expensive_columns.push(Value::record(expensive, span));
+                        }
+                    }
+                }
+                selected_columns.extend(
+                    statement
+                        .selected_expr_titles
+                        .iter()
+                        .map(|title| Value::string(title, span)),
+                );
+                step.insert("distinct", Value::bool(distinct(statement), span));
+            }
+            Statement::Where(statement) => {
+                filters.push(filter(name, statement.condition.as_ref(), span));
+            }
+            Statement::Having(statement) => {
+                filters.push(filter(name, statement.condition.as_ref(), span));
+            }
+            Statement::Qualify(statement) => {
+                filters.push(filter(name, statement.condition.as_ref(), span));
+            }
+            Statement::Limit(statement) => {
+                step.insert("count", Value::int(statement.count as i64, span));
+            }
+            Statement::Offset(statement) => {
+                step.insert("start", expr_to_value(statement.start.as_ref(), span));
+            }
+            Statement::OrderBy(statement) => {
+                let arguments = statement
+                    .arguments
+                    .iter()
+                    .map(|argument| expr_to_value(argument.as_ref(), span))
+                    .collect();
+                step.insert("arguments", Value::list(arguments, span));
+            }
+            Statement::GroupBy(statement) => {
+                let values = statement
+                    .values
+                    .iter()
+                    .map(|value| expr_to_value(value.as_ref(), span))
+                    .collect();
+                step.insert("values", Value::list(values, span));
+                step.insert("with_rollup", Value::bool(statement.has_with_roll_up, span));
+            }
+            Statement::AggregateFunction(statement) => {
+                let mut names: Vec<&String> = statement.aggregations.keys().collect();
+                names.sort();
+                let names = names.into_iter().map(|name| Value::string(name, span));
+                step.insert("aggregations", Value::list(names.collect(), span));
+            }
+            Statement::WindowFunction(statement) => {
+                let mut names: Vec<&String> = statement.window_values.keys().collect();
+                names.sort();
+                let names = names.into_iter().map(|name| Value::string(name, span));
+                step.insert("window_values", Value::list(names.collect(), span));
+            }
+            Statement::Into(statement) => {
+                step.insert("file_path", Value::string(&statement.file_path, span));
+            }
+        }
+        statements.push(Value::record(step, span));
+    }
+
+    let mut record = Record::new();
+    record.insert("query", Value::string("select", span));
+    record.insert("tables", Value::list(tables, span));
+    record.insert("selected_columns", Value::list(selected_columns, span));
+    record.insert("columns_read", Value::list(columns_read, span));
+    record.insert("filters", Value::list(filters, span));
+    record.insert("expensive_columns", Value::list(expensive_columns, span));
+    record.insert(
+        "pushdown",
+        CommitFilter::from_select(select).map_or(Value::nothing(span), |filter| {
+            pushdown_to_value(&filter, span)
+        }),
+    );
+    record.insert("statements", Value::list(statements, span));
+    Value::record(record, span)
+}
+
+/// Bounds the commit walk applies itself, so they cost no decoded commits.
+fn pushdown_to_value(filter: &CommitFilter, span: Span) -> Value {
+    let date = |seconds: Option<i64>| {
+        seconds
+            .and_then(|seconds| Utc.timestamp_opt(seconds, 0).single())
+            .map_or(Value::nothing(span), |date| Value::date(date.into(), span))
+    };
+    let text = |text: &Option<String>| {
+        text.as_ref()
+            .map_or(Value::nothing(span), |text| Value::string(text, span))
+    };
+
+    let mut record = Record::new();
+    record.insert("table", Value::string(&filter.table, span));
+    record.insert("since", date(filter.since));
+    record.insert("until", date(filter.until));
+    record.insert("author_email", text(&filter.author_email));
+    record.insert("commit_id", text(&filter.commit_id));
+    record.insert(
+        "limit",
+        filter
+            .limit
+            .map_or(Value::nothing(span), |limit| Value::int(limit as i64, span)),
+    );
+    Value::record(record, span)
+}
+
+fn distinct(statement: &gitql_ast::statement::SelectStatement) -> bool {
+    !matches!(statement.distinct, gitql_ast::statement::Distinct::None)
+}
+
+fn filter(clause: &str, condition: &dyn Expr, span: Span) -> Value {
+    let mut record = Record::new();
+    record.insert("clause", Value::string(clause, span));
+    record.insert("condition", expr_to_value(condition, span));
+    Value::record(record, span)
+}
+
+/// Expression tree as nested records, each with a `kind` and its operands.
+fn expr_to_value(expr: &dyn Expr, span: Span) -> Value {
+    let mut record = Record::new();
+    let any = expr.as_any();
+    let child = |expr: &dyn Expr| expr_to_value(expr, span);
+    let children = |exprs: &[Box<dyn Expr>]| {
+        Value::list(
+            exprs.iter().map(|expr| child(expr.as_ref())).collect(),
+            span,
+        )
+    };
+
+    let kind = match expr.kind() {
+        ExprKind::Symbol => {
+            if let Some(symbol) = any.downcast_ref::<SymbolExpr>() {
+                record.insert("name", Value::string(&symbol.value, span));
+            }
+            "column"
+        }
+        ExprKind::String => {
+            if let Some(string) = any.downcast_ref::<StringExpr>() {
+                record.insert("value", Value::string(&string.value, span));
+            }
+            "string"
+        }
+        ExprKind::Number => {
+            if let Some(number) = any.downcast_ref::<NumberExpr>() {
+                let value = match number.value {
+                    Number::Int(int) => Value::int(int, span),
+                    Number::Float(float) => Value::float(float, span),
+                };
+                record.insert("value", value);
+            }
+            "number"
+        }
+        ExprKind::Boolean => {
+            if let Some(boolean) = any.downcast_ref::<BooleanExpr>() {
+                record.insert("value", Value::bool(boolean.is_true, span));
+            }
+            "boolean"
+        }
+        ExprKind::Null => "null",
+        ExprKind::GlobalVariable => {
+            if let Some(variable) = any.downcast_ref::<GlobalVariableExpr>() {
+                record.insert("name", Value::string(&variable.name, span));
+            }
+            "variable"
+        }
+        ExprKind::Array => {
+            if let Some(array) = any.downcast_ref::<ArrayExpr>() {
+                record.insert("values", children(&array.values));
+            }
+            "array"
+        }
+        ExprKind::PrefixUnary => {
+            if let Some(unary) = any.downcast_ref::<UnaryExpr>() {
+                let operator = match unary.operator {
+                    PrefixUnaryOperator::Plus => "+",
+                    PrefixUnaryOperator::Minus => "-",
+                    PrefixUnaryOperator::Bang => "!",
+                    PrefixUnaryOperator::Not => "not",
+                };
+                record.insert("operator", Value::string(operator, span));
+                record.insert("operand", child(unary.right.as_ref()));
+            }
+            "unary"
+        }
+        ExprKind::Arithmetic => {
+            if let Some(arithmetic) = any.downcast_ref::<ArithmeticExpr>() {
+                let operator = match arithmetic.operator {
+                    ArithmeticOperator::Plus => "+",
+                    ArithmeticOperator::Minus => "-",
+                    ArithmeticOperator::Star => "*",
+                    ArithmeticOperator::Slash => "/",
+                    ArithmeticOperator::Modulus => "%",
+                    ArithmeticOperator::Exponentiation => "^",
+                };
+                record.insert("operator", Value::string(operator, span));
+                record.insert("left", child(arithmetic.left.as_ref()));
+                record.insert("right", child(arithmetic.right.as_ref()));
+            }
+            "arithmetic"
+        }
+        ExprKind::Comparison => {
+            if let Some(comparison) = any.downcast_ref::<ComparisonExpr>() {
+                record.insert(
+                    "operator",
+                    Value::string(comparison_operator(&comparison.operator), span),
+                );
+                record.insert("left", child(comparison.left.as_ref()));
+                record.insert("right", child(comparison.right.as_ref()));
+            }
+            "comparison"
+        }
+        ExprKind::Logical => {
+            if let Some(logical) = any.downcast_ref::<LogicalExpr>() {
+                let operator = match logical.operator {
+                    BinaryLogicalOperator::Or => "or",
+                    BinaryLogicalOperator::And => "and",
+                    BinaryLogicalOperator::Xor => "xor",
+                };
+                record.insert("operator", Value::string(operator, span));
+                record.insert("left", child(logical.left.as_ref()));
+                record.insert("right", child(logical.right.as_ref()));
+            }
+            "logical"
+        }
+        ExprKind::Like => {
+            if let Some(like) = any.downcast_ref::<LikeExpr>() {
+                record.insert("input", child(like.input.as_ref()));
+                record.insert("pattern", child(like.pattern.as_ref()));
+            }
+            "like"
+        }
+        ExprKind::Regex => {
+            if let Some(regex) = any.downcast_ref::<RegexExpr>() {
+                record.insert("input", child(regex.input.as_ref()));
+                record.insert("pattern", child(regex.pattern.as_ref()));
+            }
+            "regex"
+        }
+        ExprKind::Glob => {
+            if let Some(glob) = any.downcast_ref::<GlobExpr>() {
+                record.insert("input", child(glob.input.as_ref()));
+                record.insert("pattern", child(glob.pattern.as_ref()));
+            }
+            "glob"
+        }
+        ExprKind::Contains => {
+            if let Some(contains) = any.downcast_ref::<ContainsExpr>() {
+                record.insert("left", child(contains.left.as_ref()));
+                record.insert("right", child(contains.right.as_ref()));
+            }
+            "contains"
+        }
+        ExprKind::ContainedBy => {
+            if let Some(contained_by) = any.downcast_ref::<ContainedByExpr>() {
+                record.insert("left", child(contained_by.left.as_ref()));
+                record.insert("right", child(contained_by.right.as_ref()));
+            }
+            "contained_by"
+        }
+        ExprKind::Call => {
+            if let Some(call) = any.downcast_ref::<CallExpr>() {
+                record.insert("function", Value::string(&call.function_name, span));
+                record.insert("arguments", children(&call.arguments));
+            }
+            "call"
+        }
+        ExprKind::Between => {
+            if let Some(between) = any.downcast_ref::<BetweenExpr>() {
+                record.insert("value", child(between.value.as_ref()));
+                record.insert("start", child(between.range_start.as_ref()));
+                record.insert("end", child(between.range_end.as_ref()));
+            }
+            "between"
+        }
+        ExprKind::In => {
+            if let Some(in_expr) = any.downcast_ref::<InExpr>() {
+                record.insert("argument", child(in_expr.argument.as_ref()));
+                record.insert("values", children(&in_expr.values));
+                record.insert("negated", Value::bool(in_expr.has_not_keyword, span));
+            }
+            "in"
+        }
+        ExprKind::IsNull => {
+            if let Some(is_null) = any.downcast_ref::<IsNullExpr>() {
+                record.insert("argument", child(is_null.argument.as_ref()));
+                record.insert("negated", Value::bool(is_null.has_not, span));
+            }
+            "is_null"
+        }
+        ExprKind::Cast => {
+            if let Some(cast) = any.downcast_ref::<CastExpr>() {
+                record.insert("value", child(cast.value.as_ref()));
+                record.insert("type", Value::string(cast.result_type.literal(), span));
+            }
+            "cast"
+        }
+        ExprKind::Column => {
+            if let Some(column) = any.downcast_ref::<ColumnExpr>() {
+                record.insert("value", child(column.expr.as_ref()));
+            }
+            "column_expression"
+        }
+        ExprKind::Assignment => "assignment",
+        ExprKind::Interval => "interval",
+        ExprKind::Index => "index",
+        ExprKind::Slice => "slice",
+        ExprKind::GroupComparison => "group_comparison",
+        ExprKind::Bitwise => "bitwise",
+        ExprKind::BenchmarkCall => "benchmark",
+        ExprKind::Case => "case",
+        ExprKind::Row => "row",
+        ExprKind::MemberAccess => "member_access",
+    };
+
+    let mut node = Record::new();
+    node.insert("kind", Value::string(kind, span));
+    node.extend(record);
+    Value::record(node, span)
+}
+
+fn comparison_operator(operator: &ComparisonOperator) -> &'static str {
+    match operator {
+        ComparisonOperator::Greater => ">",
+        ComparisonOperator::GreaterEqual => ">=",
+        ComparisonOperator::Less => "<",
+        ComparisonOperator::LessEqual => "<=",
+        ComparisonOperator::Equal => "=",
+        ComparisonOperator::NotEqual => "!=",
+        ComparisonOperator::NullSafeEqual => "<=>",
+    }
+}
@@ -0,0 +1,293 @@
+use gitql_ast::expression::{
+    CastExpr, ComparisonExpr, Expr, LogicalExpr, Number, NumberExpr, StringExpr, SymbolExpr,
+};
+use gitql_ast::operator::{BinaryLogicalOperator, ComparisonOperator};
+use gitql_ast::query::{Query, SelectQuery};
+use gitql_ast::statement::{Distinct, SelectStatement, Statement};
+use gitql_core::values::converters::string_literal_to_date_time;
+use gitql_core::values::datetime::DateTimeValue;
+
+/// Statements that keep the provider's row order and only drop rows, so a
+/// walk that already applied the `WHERE` clause can stop at `LIMIT` rows.
+const ROW_PRESERVING_STATEMENTS: [&str; 5] = ["select", "where", "offset", "limit", "into"];
+
+/// Bounds from a query's `WHERE` and `LIMIT` clauses that the commit-walking
+/// tables apply while walking history, instead of handing every commit to
+/// the engine to filter afterwards.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct CommitFilter {
+    /// Table the bounds were read for.
+    pub table: String,
+    /// Earliest commit time to return, in seconds since the epoch.
+    pub since: Option<i64>,
+    /// Latest commit time to return, in seconds since the epoch.
+    pub until: Option<i64>,
+    pub author_email: Option<String>,
+    pub commit_id: Option<String>,
+    /// Rows the walk may stop after. Only set when every `WHERE` condition
+    /// was pushed down and nothing after the filter reorders or merges rows.
+    pub limit: Option<usize>,
+}
+
+impl CommitFilter {
+    /// Bounds for the single commit-walking table read by `queries`, or
+    /// `None` when there is nothing the walk can use.
+    pub fn from_queries(queries: &[Query]) -> Option<CommitFilter> {
+        let mut selects = queries.iter().filter_map(|query| match query {
+            Query::Select(select) => Some(select),
+            _ => None,
+        });
+        let select = selects.next()?;
+        if selects.next().is_some() {
+            return None;
+        }
+        Self::from_select(select)
+    }
+
+    /// Bounds for the commit-walking table read by one select query.
+    pub fn from_select(select: &SelectQuery) -> Option<CommitFilter> {
+        let Some(Statement::Select(statement)) = select.statements.get("select") else {
+            return None;
+        };
+        let [selection] = statement.table_selections.as_slice() else {
+            return None;
+        };
+        if !statement.joins.is_empty() {
+            return None;
+        }
+        let columns = PushdownColumns::for_table(&selection.table_name)?;
+
+        let mut filter = CommitFilter {
+            table: selection.table_name.clone(),
+            ..CommitFilter::default()
+        };
+        let mut exact = true;
+        if let Some(Statement::Where(condition)) = select.statements.get("where") {
+            for predicate in conjuncts(condition.condition.as_ref()) {
+                exact &= filter.push_predicate(predicate, &columns, statement);
+            }
+        }
+
+        let preserves_rows = select
+            .statements
+            .keys()
+            .all(|name| ROW_PRESERVING_STATEMENTS.contains(name))
+            && matches!(statement.distinct, Distinct::None);
+        if let Some(Statement::Limit(limit)) = select.statements.get("limit")
+            && exact
+            && preserves_rows
+        {
+            filter.limit = offset(select).map(|offset| offset + limit.count);
+        }
+
+        filter.has_bounds().then_some(filter)
+    }
+
+    /// Narrow the filter by one `WHERE` conjunct. Returns false when the walk
+    /// can't apply it exactly, so the engine still has rows to drop.
+    fn push_predicate(
+        &mut self,
+        predicate: &dyn Expr,
+        columns: &PushdownColumns,
+        statement: &SelectStatement,
+    ) -> bool {
+        let Some((column, operator, literal)) = column_comparison(predicate) else {
+            return false;
+        };
+        if is_aliased(statement, column) {
+            return false;
+        }
+
+        if columns.datetime == Some(column) {
+            let Some(seconds) = datetime_literal(literal) else {
+                return false;
+            };
+            let since = match operator {
+                ComparisonOperator::Greater => Some(seconds + 1),
+                ComparisonOperator::GreaterEqual | ComparisonOperator::Equal => Some(seconds),
+                _ => None,
+            };
+            let until = match operator {
+                ComparisonOperator::Less => Some(seconds - 1),
+                ComparisonOperator::LessEqual | ComparisonOperator::Equal => Some(seconds),
+                _ => None,
+            };
+            if since.is_none() && until.is_none() {
+                return false;
+            }
+            if let Some(since) = since {
+                self.since = Some(self.since.map_or(since, |current| current.max(since)));
+            }
+            if let Some(until) = until {
+                self.until = Some(self.until.map_or(until, |current| current.min(until)));
+            }
+            return true;
+        }
+
+        let target = if columns.author_email == Some(column) {
+            &mut self.author_email
+        } else if columns.commit_id == column {
+            &mut self.commit_id
+        } else {
+            return false;
+        };
+        let Some(value) = string_literal(literal) else {
+            return false;
+        };
+        if operator != ComparisonOperator::Equal {
+            return false;
+        }
+
+        match target {
+            Some(current) => current == value,
+            None => {
+                *target = Some(value.to_string());
+                true
+            }
+        }
+    }
+
+    fn has_bounds(&self) -> bool {
+        self.since.is_some()
+            || self.until.is_some()
+            || self.author_email.is_some()
+            || self.commit_id.is_some()
+            || self.limit.is_some()
+    }
+
+    pub fn matches_commit_id(&self, commit_id: &str) -> bool {
+        self.commit_id.as_deref().is_none_or(|id| id == commit_id)
+    }
+
+    /// Whether a decoded commit passes the time and author bounds.
+    pub fn matches_commit(&self, seconds: i64, author_email: Option<&str>) -> bool {
+        self.since.is_none_or(|since| seconds >= since)
+            && self.until.is_none_or(|until| seconds <= until)
+            && self
+                .author_email
+                .as_deref()
+                .is_none_or(|email| author_email == Some(email))
+    }
+
+    /// Whether the walk can stop after adding the rows of a matching commit:
+    /// a commit id matches only once, and `LIMIT` may already be satisfied.
+    pub fn is_exhausted(&self, rows: usize) -> bool {
+        self.commit_id.is_some() || self.limit.is_some_and(|limit| rows >= limit)
+    }
+}
+
+/// Columns of a commit-walking table that the walk knows how to filter on.
+struct PushdownColumns {
+    datetime: Option<&'static str>,
+    author_email: Option<&'static str>,
+    commit_id: &'static str,
+}
+
+impl PushdownColumns {
+    fn for_table(table: &str) -> Option<PushdownColumns> {
+        match table {
+            "commits" => Some(PushdownColumns {
+                datetime: Some("datetime"),
+                author_email: Some("author_email"),
+                commit_id: "commit_id",
+            }),
+            "diffs" => Some(PushdownColumns {
+                datetime: Some("datetime"),
+                author_email: Some("email"),
+                commit_id: "commit_id",
+            }),
+            "diff_files" => Some(PushdownColumns {
+                datetime: None,
+                author_email: None,
+                commit_id: "commit_id",
+            }),
+            _ => None,
+        }
+    }
+}
+
+/// Operands of a chain of `AND`s, left to right.
+fn conjuncts(expr: &dyn Expr) -> Vec<&dyn Expr> {
+    match expr.as_any().downcast_ref::<LogicalExpr>() {
+        Some(logical) if logical.operator == BinaryLogicalOperator::And => {
+            let mut operands = conjuncts(logical.left.as_ref());
+            operands.extend(conjuncts(logical.right.as_ref()));
+            operands
+        }
+        _ => vec![expr],
+    }
+}
+
+/// `column <op> literal`, with `literal <op> column` flipped to match.
+fn column_comparison(expr: &dyn Expr) -> Option<(&str, ComparisonOperator, &dyn Expr)> {
+    let comparison = expr.as_any().downcast_ref::<ComparisonExpr>()?;
+    if let Some(column) = symbol(comparison.left.as_ref()) {
+        return Some((
+            column,
+            comparison.operator.clone(),
+            comparison.right.as_ref(),
+        ));
+    }
+    let column = symbol(comparison.right.as_ref())?;
+    let operator = match comparison.operator {
+        ComparisonOperator::Greater => ComparisonOperator::Less,
+        ComparisonOperator::GreaterEqual => ComparisonOperator::LessEqual,
+        ComparisonOperator::Less => ComparisonOperator::Greater,
+        ComparisonOperator::LessEqual => ComparisonOperator::GreaterEqual,
+        ref operator => operator.clone(),
+    };
+    Some((column, operator, comparison.left.as_ref()))
+}
+
+/// Whether `column` in the filter names a selected expression's alias
+/// rather than the table column itself.
+fn is_aliased(statement: &SelectStatement, column: &str) -> bool {
+    statement
+        .selected_expr_titles
+        .iter()
+        .zip(&statement.selected_expr)
+        .any(|(title, expr)| {
+            title == column
+                && expr
+                    .as_any()
+                    .downcast_ref::<SymbolExpr>()
+                    .is_none_or(|symbol| symbol.value != column)
+        })
+}
+
+fn symbol(expr: &dyn Expr) -> Option<&str> {
+    expr.as_any()
+        .downcast_ref::<SymbolExpr>()
+        .map(|symbol| symbol.value.as_str())
+}
+
+fn string_literal(expr: &dyn Expr) -> Option<&str> {
+    expr.as_any()
+        .downcast_ref::<StringExpr>()
+        .map(|string| string.value.as_str())
+}
+
+/// Seconds since the epoch of a datetime literal, converted the same way the
+/// engine casts it so the walk and the engine agree on the bound.
+fn datetime_literal(expr: &dyn Expr) -> Option<i64> {
+    let cast = expr.as_any().downcast_ref::<CastExpr>()?;
+    if !cast.result_type.is_date_time() {
+        return None;
+    }
+    let value = string_literal_to_date_time(string_literal(cast.value.as_ref())?);
+    value
+        .as_any()
+        .downcast_ref::<DateTimeValue>()
+        .map(|datetime| datetime.value)
+}
+
+/// Rows skipped by `OFFSET`, when it is a literal the walk can add to `LIMIT`.
+fn offset(select: &SelectQuery) -> Option<usize> {
+    let Some(Statement::Offset(offset)) = select.statements.get("offset") else {
+        return Some(0);
+    };
+    match offset.start.as_any().downcast_ref::<NumberExpr>()?.value {
+        Number::Int(start) => usize::try_from(start).ok(),
+        Number::Float(_) => None,
+    }
+}
@@ -1,5 +1,6 @@
 use gitql_ast::types::{
-    boolean::BoolType, date::DateType, integer::IntType, text::TextType, DataType,
+    boolean::BoolType, date::DateType, datetime::DateTimeType, integer::IntType, text::TextType,
+    DataType,
 };
 use std::collections::HashMap;
 use std::sync::OnceLock;
@@ -20,12 +21,65 @@ pub fn tables_fields_types() -> HashMap<&'static str, Box<dyn DataType>> {
     map.insert("files_changed", Box::new(IntType));
     map.insert("email", Box::new(TextType));
     map.insert("type", Box::new(TextType));
-    map.insert("datetime", Box::new(DateType));
+    map.insert("datetime", Box::new(DateTimeType));
     map.insert("is_head", Box::new(BoolType));
     map.insert("is_remote", Box::new(BoolType));
     map.insert("commit_count", Box::new(IntType));
     map.insert("parents_count", Box::new(IntType));
     map.insert("updated", Box::new(DateType));
+    map.insert("path", Box::new(TextType));
+    map.insert("old_path", Box::new(TextType));
+    map.insert("change_type", Box::new(TextType));
+    map.insert("is_binary", Box::new(BoolType));
+    map.insert("old_mode", Box::new(TextType));
+    map.insert("new_mode", Box::new(TextType));
+    map.insert("line_number", Box::new(IntType));
+    map.insert("original_line_number", Box::new(IntType));
+    map.insert("content", Box::new(TextType));
+    map.insert("extension", Box::new(TextType));
+    map.insert("mode", Box::new(TextType));
+    map.insert("size", Box::new(IntType));
+    map.insert("blob_id", Box::new(TextType));
+    map.insert("is_executable", Box::new(BoolType));
+    map.insert("is_symlink", Box::new(BoolType));
+    map.insert("is_submodule", Box::new(BoolType));
+    map.insert("depth", Box::new(IntType));
+    map.insert("target_id", Box::new(TextType));
+    map.insert("target_type", Box::new(TextType));
+    map.insert("is_annotated", Box::new(BoolType));
+    map.insert("tagger_name", Box::new(TextType));
+    map.insert("tagger_email", Box::new(TextType));
+    map.insert("tagger_datetime", Box::new(DateTimeType));
+    map.insert("commit_datetime", Box::new(DateTimeType));
+    map.insert("upstream", Box::new(TextType));
+    map.insert("ahead", Box::new(IntType));
+    map.insert("behind", Box::new(IntType));
+    map.insert("merge_base_id", Box::new(TextType));
+    map.insert("is_merged_into_head", Box::new(BoolType));
+    map.insert("index_status", Box::new(TextType));
+    map.insert("worktree_status", Box::new(TextType));
+    map.insert("is_untracked", Box::new(BoolType));
+    map.insert("is_ignored", Box::new(BoolType));
+    map.insert("is_conflicted", Box::new(BoolType));
+    map.insert("rename_from", Box::new(TextType));
+    map.insert("fetch_url", Box::new(TextType));
+    map.insert("push_url", Box::new(TextType));
+    map.insert("fetch_refspecs", Box::new(TextType));
+    map.insert("push_refspecs", Box::new(TextType));
+    map.insert("tag_mode", Box::new(TextType));
+    map.insert("is_default", Box::new(BoolType));
+    map.insert("index", Box::new(IntType));
+    map.insert("branch", Box::new(TextType));
+    map.insert("ref_name", Box::new(TextType));
+    map.insert("old_id", Box::new(TextType));
+    map.insert("new_id", Box::new(TextType));
+    map.insert("operation", Box::new(TextType));
+    map.insert("id", Box::new(TextType));
+    map.insert("head_commit", Box::new(TextType));
+    map.insert("is_main", Box::new(BoolType));
+    map.insert("is_locked", Box::new(BoolType));
+    map.insert("lock_reason", Box::new(TextType));
+    map.insert("is_prunable", Box::new(BoolType));
     map.insert("repo", Box::new(TextType));
     map.insert("repo_name", Box::new(TextType));
     map
@@ -52,6 +106,27 @@ pub fn describe_table(table: &str) -> Option<Vec<(&'static str, String)>> {
     })
 }
 
+/// Why reading `column` from `table` costs far more than the rest of a row,
+/// or `None` for ordinary columns.
+pub fn expensive_column_reason(table: &str, column: &str) -> Option<&'static str> {
+    match (table, column) {
+        ("diffs", "insertions" | "deletions") => {
+            Some("line diff of every file changed by each commit")
+        }
+        ("diffs", "files_changed") => Some("tree diff of each commit against its parent"),
+        ("diff_files", "insertions" | "deletions" | "is_binary") => {
+            Some("line diff of each changed file")
+        }
+        ("branches", "commit_count") => Some("walks the full history of every branch"),
+        ("branches", "ahead" | "behind" | "merge_base_id") => {
+            Some("history walk between each branch and its upstream")
+        }
+        ("branches", "is_merged_into_head") => Some("merge-base search for every branch"),
+        ("stashes", "files_changed") => Some("tree diff of each stash against its base commit"),
+        _ => None,
+    }
+}
+
 pub fn tables_fields_names() -> &'static HashMap<&'static str, Vec<&'static str>> {
     static HASHMAP: OnceLock<HashMap<&'static str, Vec<&'static str>>> = OnceLock::new();
     HASHMAP.get_or_init(|| {
@@ -84,6 +159,11 @@ pub fn tables_fields_names() -> &'static HashMap<&'static str, Vec<&'static str>
                 "is_head",
                 "is_remote",
                 "updated",
+                "upstream",
+                "ahead",
+                "behind",
+                "merge_base_id",
+                "is_merged_into_head",
                 "repo",
                 "repo_name",
             ],
@@ -102,7 +182,145 @@ pub fn tables_fields_names() -> &'static HashMap<&'static str, Vec<&'static str>
                 "repo_name",
             ],
         );
-        map.insert("tags", vec!["name", "repo", "repo_name"]);
+        map.insert(
+            "diff_files",
+            vec![
+                "commit_id",
+                "path",
+                "old_path",
+                "change_type",
+                "insertions",
+                "deletions",
+                "is_binary",
+                "old_mode",
+                "new_mode",
+                "repo",
+                "repo_name",
+            ],
+        );
+        map.insert(
+            "tags",
+            vec![
+                "name",
+                "full_name",
+                "target_id",
+                "target_type",
+                "is_annotated",
+                "tagger_name",
+                "tagger_email",
+                "tagger_datetime",
+                "message",
+                "title",
+                "commit_datetime",
+                "repo",
+                "repo_name",
+            ],
+        );
+        map.insert(
+            "blame",
+            vec![
+                "path",
+                "line_number",
+                "commit_id",
+                "author_name",
+                "author_email",
+                "datetime",
+                "content",
+                "original_line_number",
+                "repo",
+                "repo_name",
+            ],
+        );
+        map.insert(
+            "files",
+            vec![
+                "path",
+                "name",
+                "extension",
+                "mode",
+                "size",
+                "blob_id",
+                "is_executable",
+                "is_symlink",
+                "is_submodule",
+                "depth",
+                "repo",
+                "repo_name",
+            ],
+        );
+        map.insert(
+            "status",
+            vec![
+                "path",
+                "index_status",
+                "worktree_status",
+                "is_untracked",
+                "is_ignored",
+                "is_conflicted",
+                "rename_from",
+                "repo",
+                "repo_name",
+            ],
+        );
+        map.insert(
+            "remotes",
+            vec![
+                "name",
+                "fetch_url",
+                "push_url",
+                "fetch_refspecs",
+                "push_refspecs",
+                "tag_mode",
+                "is_default",
+                "repo",
+                "repo_name",
+            ],
+        );
+        map.insert(
+            "stashes",
+            vec![
+                "index",
+                "commit_id",
+                "message",
+                "branch",
+                "datetime",
+                "author_name",
+                "files_changed",
+                "repo",
+                "repo_name",
+            ],
+        );
+        map.insert(
+            "reflog",
+            vec![
+                "ref_name",
+                "index",
+                "old_id",
+                "new_id",
+                "committer_name",
+                "committer_email",
+                "datetime",
+                "message",
+                "operation",
+                "repo",
+                "repo_name",
+            ],
+        );
+        map.insert(
+            "worktrees",
+            vec![
+                "id",
+                "path",
+                "head_commit",
+                "branch",
+                "is_main",
+                "is_locked",
+                "lock_reason",
+                "is_prunable",
+                "repo",
+                "repo_name",
+            ],
+        );
         map
     })
 }
@@ -113,7 +331,21 @@ mod tests {
 
     #[test]
     fn table_definitions_expose_repo_name() {
-        for table in ["refs", "commits", "branches", "diffs", "tags"] {
+        for table in [
+            "refs",
+            "commits",
+            "branches",
+            "diffs",
+            "diff_files",
+            "tags",
+            "blame",
+            "files",
+            "status",
+            "remotes",
+            "stashes",
+            "reflog",
+            "worktrees",
+        ] {
             let fields = tables_fields_names().get(table).expect("table exists");
             assert!(
                 fields.contains(&"repo_name"),
@@ -2,24 +2,34 @@
 #![warn(clippy::unchecked_time_subtraction)]
 
 use crate::gitql_schema::{tables_fields_names, tables_fields_types};
-use gitql_cli::{arguments::Arguments, diagnostic_reporter, printer::OutputFormatKind};
+use gitql_ast::format_checker::is_valid_date_format;
+use gitql_ast::query::Query;
+use gitql_cli::diagnostic_reporter;
 use gitql_core::{environment::Environment, object::GitQLObject, schema::Schema};
-use gitql_data_provider::GitDataProvider;
+use gitql_data_provider::{GitDataProvider, ProvideTiming, RepoError};
 use gitql_engine::{data_provider::DataProvider, engine, engine::EvaluationResult::SelectedGroups};
 use gitql_parser::diagnostic::Diagnostic;
+use gitql_parser::token::{Token, TokenKind};
 use gitql_parser::{parser, tokenizer};
+use gitql_pushdown::CommitFilter;
 use gitql_std::aggregation::{aggregation_function_signatures, aggregation_functions};
 use nu_plugin::{
     serve_plugin, EngineInterface, EvaluatedCall, MsgPackSerializer, Plugin, PluginCommand,
-    SimplePluginCommand,
 };
-use nu_protocol::{Category, Example, LabeledError, Signature, Span, SyntaxShape, Value};
+use nu_protocol::{
+    Category, Example, LabeledError, ListStream, PipelineData, Signature, Span, SyntaxShape, Value,
+};
 use std::path::Path;
+use std::sync::mpsc;
+use std::time::{Duration, Instant};
 
 mod gitql_data_provider;
+mod gitql_explain;
 mod gitql_functions;
+mod gitql_pushdown;
 mod gitql_schema;
 mod nushell_render;
+mod repo_discovery;
 
 pub struct GitqlPlugin;
 
@@ -40,7 +50,11 @@ impl Plugin for GitqlPlugin {
 
 pub struct Gitql;
 
-impl SimplePluginCommand for Gitql {
+/// Rendered rows buffered between the query thread and Nushell before the
+/// query thread blocks and waits for the consumer.
+const STREAM_BUFFER_ROWS: usize = 256;
+
+impl PluginCommand for Gitql {
     type Plugin = GitqlPlugin;
 
     fn name(&self) -> &str {
@@ -62,10 +76,27 @@ impl SimplePluginCommand for Gitql {
                 "Repository paths to query",
                 Some('R'),
             )
+            .named(
+                "discover",
+                SyntaxShape::String,
+                "Query every git repository found under this directory",
+                Some('d'),
+            )
+            .named(
+                "max-depth",
+                SyntaxShape::Int,
+                "How many directories below --discover to search",
+                None,
+            )
+            .switch(
+                "include-submodules",
+                "Also query the submodules of checkouts found by --discover",
+                None,
+            )
             .named(
                 "output",
                 SyntaxShape::String,
-                "Output format: table, json, csv, yaml",
+                "Output format: table, json, ndjson, csv, tsv, yaml, markdown, html",
                 Some('o'),
             )
             .named(
@@ -79,7 +110,68 @@ impl SimplePluginCommand for Gitql {
                 "Limit output to a single page of results",
                 Some('p'),
             )
-            .switch("analysis", "Show query analysis timings", Some('a'))
+            .named(
+                "page",
+                SyntaxShape::Int,
+                "Page of results to return, starting at 1 (implies --pagination)",
+                None,
+            )
+            .named(
+                "offset",
+                SyntaxShape::Int,
+                "Number of result rows to skip before the page (implies --pagination)",
+                None,
+            )
+            .switch(
+                "analysis",
+                "Return the result with a record of query timings and row counts",
+                Some('a'),
+            )
+            .switch(
+                "raw",
+                "Return json, yaml and csv output as text instead of Nushell values",
+                None,
+            )
+            .named(
+                "nest-by",
+                SyntaxShape::String,
+                "Return a record keyed by each value of this result column (table, json, yaml)",
+                None,
+            )
+            .named(
+                "rev",
+                SyntaxShape::String,
+                "Revision to walk history from instead of HEAD (commits, diffs, diff_files), or to read files and blame at",
+                None,
+            )
+            .named(
+                "range",
+                SyntaxShape::String,
+                "Revision range to walk, e.g. v1.0..v2.0 or main...feature (commits, diffs, diff_files)",
+                None,
+            )
+            .named(
+                "path",
+                SyntaxShape::String,
+                "Repository-relative file path for the blame table",
+                None,
+            )
+            .switch(
+                "all-refs",
+                "Read the reflog of every ref instead of only HEAD (reflog)",
+                None,
+            )
+            .switch(
+                "keep-going",
+                "Skip repositories that fail and list them under errors instead of failing the query",
+                Some('k'),
+            )
+            .named(
+                "threads",
+                SyntaxShape::Int,
+                "Most repositories to read at the same time (defaults to the number of CPUs)",
+                None,
+            )
             .category(Category::Experimental)
     }
 
@@ -94,19 +186,34 @@ impl SimplePluginCommand for Gitql {
                 description: "Show the tables available to be queried",
                 result: None,
             },
+            Example {
+                example: "query git 'explain select commit_id, insertions from diffs where name = \"me\"'",
+                description: "Show the query plan, filters and expensive columns without running it",
+                result: None,
+            },
             Example {
                 example: "query git 'select * from refs limit 10'",
                 description: "Show the first 10 refs",
                 result: None,
             },
             Example {
-                example: "query git 'describe commits' --output yaml",
+                example: "query git 'describe commits' --output yaml --raw",
                 description: "Show the commits schema as YAML (JSON, YAML, CSV)",
                 result: None,
             },
             Example {
-                example: "query git 'select title, datetime from commits' --repo . --output csv",
-                description: "Query commits from the current repo and return CSV (JSON, YAML, CSV)",
+                example: "query git 'select title, datetime from commits' --repo . --output csv --raw",
+                description: "Query commits from the current repo and return CSV text (JSON, YAML, CSV)",
+                result: None,
+            },
+            Example {
+                example: "query git 'select title, author_name from commits limit 3' --output markdown",
+                description: "Show recent commits as a Markdown table (also ndjson, tsv, html)",
+                result: None,
+            },
+            Example {
+                example: "query git 'select author_name, title from commits' --nest-by author_name",
+                description: "Group commit titles under each author",
                 result: None,
             },
             Example {
@@ -115,13 +222,33 @@ impl SimplePluginCommand for Gitql {
                 result: None,
             },
             Example {
-                example: "query git 'select * from refs' --pagination --page-size 20",
+                example: "query git 'select * from refs' --pagination --page-size 20 | get rows",
                 description: "Limit output to the first 20 rows of results",
                 result: None,
             },
+            Example {
+                example: "query git 'select title from commits' --page 3 --page-size 50 | get rows",
+                description: "Return the third page of 50 commits",
+                result: None,
+            },
             Example {
                 example: "query git 'select count(*) from commits' --analysis",
-                description: "Run a query and print analysis timing information",
+                description: "Run a query and return its result with analysis timings",
+                result: None,
+            },
+            Example {
+                example: "query git 'select title, datetime from commits' --range v0.23.0..v0.24.0",
+                description: "Show the commits between two tags",
+                result: None,
+            },
+            Example {
+                example: "query git 'select line_number, author_name, content from blame' --path src/main.rs",
+                description: "Show who last changed each line of a file",
+                result: None,
+            },
+            Example {
+                example: "query git 'select path, size from files order by size desc limit 10'",
+                description: "Show the ten largest files at HEAD",
                 result: None,
             },
             Example {
@@ -137,8 +264,8 @@ impl SimplePluginCommand for Gitql {
         _plugin: &GitqlPlugin,
         engine: &EngineInterface,
         call: &EvaluatedCall,
-        _input: &Value,
-    ) -> Result<Value, LabeledError> {
+        _input: PipelineData,
+    ) -> Result<PipelineData, LabeledError> {
         let curdir = engine.get_current_dir()?;
         let query_string: String = call.req(0)?;
 
@@ -148,6 +275,15 @@ impl SimplePluginCommand for Gitql {
         let repos_flag: Option<Value> = call
             .get_flag("repos")
             .map_err(|err| LabeledError::new(err.to_string()))?;
+        let discover_flag: Option<String> = call
+            .get_flag("discover")
+            .map_err(|err| LabeledError::new(err.to_string()))?;
+        let max_depth_flag: Option<i64> = call
+            .get_flag("max-depth")
+            .map_err(|err| LabeledError::new(err.to_string()))?;
+        let include_submodules = call
+            .has_flag("include-submodules")
+            .map_err(|err| LabeledError::new(err.to_string()))?;
         let output_flag: Option<String> = call
             .get_flag("output")
             .map_err(|err| LabeledError::new(err.to_string()))?;
@@ -157,167 +293,708 @@ impl SimplePluginCommand for Gitql {
         let pagination = call
             .has_flag("pagination")
             .map_err(|err| LabeledError::new(err.to_string()))?;
+        let page_flag: Option<i64> = call
+            .get_flag("page")
+            .map_err(|err| LabeledError::new(err.to_string()))?;
+        let offset_flag: Option<i64> = call
+            .get_flag("offset")
+            .map_err(|err| LabeledError::new(err.to_string()))?;
         let analysis = call
             .has_flag("analysis")
             .map_err(|err| LabeledError::new(err.to_string()))?;
+        let rev_flag: Option<String> = call
+            .get_flag("rev")
+            .map_err(|err| LabeledError::new(err.to_string()))?;
+        let range_flag: Option<String> = call
+            .get_flag("range")
+            .map_err(|err| LabeledError::new(err.to_string()))?;
+        let path_flag: Option<String> = call
+            .get_flag("path")
+            .map_err(|err| LabeledError::new(err.to_string()))?;
+        let all_refs = call
+            .has_flag("all-refs")
+            .map_err(|err| LabeledError::new(err.to_string()))?;
+        let nest_by_flag: Option<String> = call
+            .get_flag("nest-by")
+            .map_err(|err| LabeledError::new(err.to_string()))?;
+        let raw = call
+            .has_flag("raw")
+            .map_err(|err| LabeledError::new(err.to_string()))?;
+        let threads_flag: Option<i64> = call
+            .get_flag("threads")
+            .map_err(|err| LabeledError::new(err.to_string()))?;
+        let keep_going = call
+            .has_flag("keep-going")
+            .map_err(|err| LabeledError::new(err.to_string()))?;
+        if rev_flag.is_some() && range_flag.is_some() {
+            return Err(LabeledError::new("Use either --rev or --range, not both")
+                .with_label("conflicting revision flags", call.head));
+        }
+        if let Some(rev) = rev_flag.as_deref().filter(|rev| is_revision_range(rev)) {
+            return Err(LabeledError::new(format!(
+                "--rev takes a single revision, use --range for '{rev}'"
+            ))
+            .with_label("revision range passed to --rev", call.head));
+        }
+        if let Some(range) = range_flag
+            .as_deref()
+            .filter(|range| !is_revision_range(range))
+        {
+            return Err(LabeledError::new(format!(
+                "--range takes an a..b or a...b range, use --rev for '{range}'"
+            ))
+            .with_label("not a revision range", call.head));
+        }
+
+        if page_flag.is_some() && offset_flag.is_some() {
+            return Err(LabeledError::new("Use either --page or --offset, not both")
+                .with_label("conflicting pagination flags", call.head));
+        }
+        if page_flag.is_some_and(|page| page < 1) {
+            return Err(
+                LabeledError::new("--page starts at 1").with_label("invalid page", call.head)
+            );
+        }
+        if offset_flag.is_some_and(|offset| offset < 0) {
+            return Err(LabeledError::new("--offset can't be negative")
+                .with_label("invalid offset", call.head));
+        }
+        if discover_flag.is_some() && (repo_flag.is_some() || repos_flag.is_some()) {
+            return Err(
+                LabeledError::new("Use either --discover or --repo/--repos, not both")
+                    .with_label("conflicting repository flags", call.head),
+            );
+        }
+        if discover_flag.is_none() && (max_depth_flag.is_some() || include_submodules) {
+            return Err(LabeledError::new(
+                "--max-depth and --include-submodules only apply to --discover",
+            )
+            .with_label("missing --discover", call.head));
+        }
+        let max_depth = max_depth_flag
+            .map(usize::try_from)
+            .transpose()
+            .map_err(|_| {
+                LabeledError::new("--max-depth can't be negative")
+                    .with_label("invalid depth", call.head)
+            })?;
+        if threads_flag.is_some_and(|threads| threads < 1) {
+            return Err(LabeledError::new("--threads must be at least 1")
+                .with_label("invalid thread count", call.head));
+        }
 
-        let repo_paths = parse_repo_paths(&curdir, repo_flag, repos_flag)?;
+        let repo_paths = match discover_flag {
+            Some(dir) => {
+                discover_repo_paths(&curdir, &dir, max_depth, include_submodules, call.head)?
+            }
+            None => parse_repo_paths(&curdir, repo_flag, repos_flag)?,
+        };
         let output_format = resolve_output_format(output_flag);
 
-        let query_arguments = Arguments {
+        let page_size = page_size_flag.unwrap_or(10).max(1) as usize;
+        // --page and --offset were rejected together above.
+        let offset = match page_flag {
+            Some(page) => page_start(page, page_size).ok_or_else(|| {
+                LabeledError::new(format!("--page {page} is past the last possible row"))
+                    .with_label("page out of range", call.head)
+            })?,
+            None => offset_flag.unwrap_or(0) as usize,
+        };
+        let query_arguments = QueryArguments {
             repos: repo_paths,
             output_format,
-            pagination,
-            page_size: page_size_flag.unwrap_or(10).max(1) as usize,
+            pagination: pagination || page_flag.is_some() || offset_flag.is_some(),
+            page_size,
+            offset,
             analysis,
-            enable_line_editor: false,
+            nest_by: nest_by_flag,
+        };
+
+        let output_options = OutputOptions {
+            span: call.head,
+            raw,
         };
 
         let mut reporter = diagnostic_reporter::DiagnosticReporter::default();
-        if let Some(schema_value) =
-            render_schema_query(&query_string, &query_arguments.output_format)
-        {
-            return Ok(schema_value);
+        if let Some(schema_value) = render_schema_query(
+            &query_string,
+            &query_arguments.output_format,
+            output_options,
+        ) {
+            return Ok(PipelineData::Value(schema_value, None));
+        }
+        if let Some(explained_query) = strip_explain(&query_string) {
+            return explain_gitql_query(explained_query, call.head)
+                .map(|plan| PipelineData::Value(plan, None));
         }
 
-        let repos = match validate_git_repositories(&query_arguments.repos) {
-            Ok(repos) => repos,
-            Err(error) => {
-                reporter.report_diagnostic(&query_string, Diagnostic::error(error.as_str()));
-                return Err(LabeledError::new("Invalid repositories paths"));
+        let (repos, open_errors) = if keep_going {
+            open_git_repositories(&query_arguments.repos)
+        } else {
+            match validate_git_repositories(&query_arguments.repos) {
+                Ok(repos) => (repos, vec![]),
+                Err(error) => {
+                    reporter.report_diagnostic(&query_string, Diagnostic::error(error.as_str()));
+                    return Err(LabeledError::new("Invalid repositories paths")
+                        .with_label(error, call.head));
+                }
             }
         };
-        let schema = Schema {
-            tables_fields_names: tables_fields_names().to_owned(),
-            tables_fields_types: tables_fields_types().to_owned(),
-        };
+        let provider = GitDataProvider::new(repos)
+            .with_revision(rev_flag.or(range_flag))
+            .with_path(path_flag)
+            .with_all_refs(all_refs)
+            .with_signals(engine.signals().clone())
+            .with_threads(threads_flag.map_or_else(
+                || std::thread::available_parallelism().map_or(1, |threads| threads.get()),
+                |threads| threads as usize,
+            ))
+            .with_keep_going(keep_going, open_errors);
+
+        // GitQL rows aren't `Send`, so the query runs and renders on its own
+        // thread and hands finished Nushell rows over a bounded channel. The
+        // engine evaluates the whole query before the first row is rendered,
+        // so only rendering streams: a consumer like `first 20` stops the
+        // rendering by dropping the stream, but `LIMIT` in the query is what
+        // bounds how much history the provider reads.
+        let (sender, receiver) = mpsc::sync_channel(STREAM_BUFFER_ROWS);
+        std::thread::spawn(move || {
+            let mut env = gitql_environment();
+            match execute_gitql_query(
+                query_string,
+                &query_arguments,
+                provider,
+                &mut env,
+                output_options,
+            ) {
+                Ok(QueryOutput::Rows(mut groups)) => {
+                    let mut rows = nushell_render::render_rows(&mut groups, output_options.span);
+                    let Some(first_row) = rows.next() else {
+                        let _ = sender.send(StreamMessage::Value(Value::string(
+                            "No data to display",
+                            output_options.span,
+                        )));
+                        return;
+                    };
+                    for row in std::iter::once(first_row).chain(rows) {
+                        if sender.send(StreamMessage::Row(row)).is_err() {
+                            return;
+                        }
+                    }
+                    let _ = sender.send(StreamMessage::End);
+                }
+                Ok(QueryOutput::Value(value)) => {
+                    let _ = sender.send(StreamMessage::Value(value));
+                }
+                Err(error) => {
+                    let _ = sender.send(StreamMessage::Error(error));
+                }
+            }
+        });
 
-        let std_signatures = gitql_functions::gitql_std_signatures();
-        let std_functions = gitql_functions::gitql_std_functions();
+        // The channel only disconnects early when the query thread panicked.
+        let query_crashed = |span| {
+            LabeledError::new("The query stopped unexpectedly")
+                .with_label("query thread panicked before returning all rows", span)
+        };
+        match receiver.recv() {
+            Ok(StreamMessage::Row(first_row)) => {
+                let span = call.head;
+                let mut finished = false;
+                let rest = std::iter::from_fn(move || {
+                    if finished {
+                        return None;
+                    }
+                    match receiver.recv() {
+                        Ok(StreamMessage::Row(row)) => Some(row),
+                        Ok(_) => {
+                            finished = true;
+                            None
+                        }
+                        Err(_) => {
+                            finished = true;
+                            Some(Value::error(query_crashed(span).into(), span))
+                        }
+                    }
+                });
+                Ok(PipelineData::ListStream(
+                    ListStream::new(
+                        std::iter::once(first_row).chain(rest),
+                        call.head,
+                        engine.signals().clone(),
+                    ),
+                    None,
+                ))
+            }
+            Ok(StreamMessage::Value(value)) => Ok(PipelineData::Value(value, None)),
+            Ok(StreamMessage::Error(error)) => Err(error),
+            Ok(StreamMessage::End) | Err(_) => Err(query_crashed(call.head)),
+        }
+    }
+}
 
-        let aggregation_signatures = aggregation_function_signatures();
-        let aggregation_functions = aggregation_functions();
+/// What the query thread sends back to the plugin call.
+enum StreamMessage {
+    Row(Value),
+    /// Sent after the last row, so a disconnect before it means the query
+    /// thread panicked.
+    End,
+    Value(Value),
+    Error(LabeledError),
+}
 
-        let mut env = Environment::new(schema);
-        env.with_standard_functions(&std_signatures, std_functions);
-        env.with_aggregation_functions(&aggregation_signatures, aggregation_functions);
+/// Query settings gathered from the command's flags.
+struct QueryArguments {
+    repos: Vec<String>,
+    output_format: OutputFormat,
+    pagination: bool,
+    page_size: usize,
+    /// Result rows skipped before the page starts.
+    offset: usize,
+    analysis: bool,
+    /// Result column whose values key a nested record of rows.
+    nest_by: Option<String>,
+}
 
-        execute_gitql_query(query_string, &query_arguments, &repos, &mut env)
+/// Formats accepted by `--output`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum OutputFormat {
+    Table,
+    Json,
+    Ndjson,
+    Csv,
+    Tsv,
+    Yaml,
+    Markdown,
+    Html,
+}
 
-        // Ok(Value::nothing(call.head))
+impl OutputFormat {
+    /// Data formats with a Nushell equivalent, returned as values unless
+    /// `--raw` is set. Markdown and HTML are always text.
+    fn is_structured(self) -> bool {
+        matches!(
+            self,
+            OutputFormat::Json
+                | OutputFormat::Ndjson
+                | OutputFormat::Csv
+                | OutputFormat::Tsv
+                | OutputFormat::Yaml
+        )
     }
 }
 
+/// How query results are turned into Nushell values.
+#[derive(Clone, Copy)]
+struct OutputOptions {
+    /// Span of the `query git` call, attached to every value and error.
+    span: Span,
+    /// Return structured formats as serialized text instead of values.
+    raw: bool,
+}
+
+/// Result of a query: selected rows still to be rendered as a table, or an
+/// output that has already been rendered to a single value.
+enum QueryOutput {
+    Rows(GitQLObject),
+    Value(Value),
+}
+
+fn gitql_environment() -> Environment {
+    let schema = Schema {
+        tables_fields_names: tables_fields_names().to_owned(),
+        tables_fields_types: tables_fields_types().to_owned(),
+    };
+
+    let std_signatures = gitql_functions::gitql_std_signatures();
+    let std_functions = gitql_functions::gitql_std_functions();
+
+    let aggregation_signatures = aggregation_function_signatures();
+    let aggregation_functions = aggregation_functions();
+
+    let mut env = Environment::new(schema);
+    env.with_standard_functions(&std_signatures, std_functions);
+    env.with_aggregation_functions(&aggregation_signatures, aggregation_functions);
+    env
+}
+
 fn main() {
     serve_plugin(&GitqlPlugin, MsgPackSerializer);
 }
 
 fn execute_gitql_query(
     query: String,
-    query_arguments: &Arguments,
-    repos: &[gix::Repository],
+    query_arguments: &QueryArguments,
+    provider: GitDataProvider,
     env: &mut Environment,
-) -> Result<Value, LabeledError> {
-    let front_start = std::time::Instant::now();
-    let normalized_query = normalize_query(&query);
-    let tokens = match tokenizer::Tokenizer::tokenize(&normalized_query) {
-        Ok(tokens) => tokens,
-        Err(diagnostic) => {
-            let diagnostic = *diagnostic;
-            return Err(diagnostic_to_labeled_error(&query, diagnostic));
-        }
-    };
-    if tokens.is_empty() {
-        return Err(LabeledError::new("No tokens to parse"));
-    }
+    output_options: OutputOptions,
+) -> Result<QueryOutput, LabeledError> {
+    let span = output_options.span;
+    let tokenize_start = Instant::now();
+    let tokens = tokenize_gitql_query(&query, span)?;
+    let tokenize_duration = tokenize_start.elapsed();
 
     // eprintln!("3");
-    let query_node = match parser::parse_gql(tokens, env) {
-        Ok(query_node) => query_node,
-        Err(diagnostic) => {
-            let diagnostic = *diagnostic;
-            return Err(diagnostic_to_labeled_error(&query, diagnostic));
-        }
-    };
-    let front_duration = front_start.elapsed();
-
-    let engine_start = std::time::Instant::now();
-    let provider: Box<dyn DataProvider> = Box::new(GitDataProvider::new(repos.to_vec()));
+    let parse_start = Instant::now();
+    let query_node = parse_gitql_tokens(&query, tokens, env, span)?;
+    let parse_duration = parse_start.elapsed();
+
+    let evaluate_start = Instant::now();
+    let provider = provider.with_commit_filter(CommitFilter::from_queries(&query_node));
+    let signals = provider.signals.clone();
+    let provide_timings = provider.timings.clone();
+    let repo_errors = provider.keep_going.then(|| provider.errors.clone());
+    let provider: Box<dyn DataProvider> = Box::new(provider);
     let engine_results = match engine::evaluate(env, &provider, query_node) {
         Ok(results) => results,
+        Err(_) if signals.interrupted() => {
+            return Err(LabeledError::new("Query interrupted")
+                .with_label("interrupted", span)
+                .with_help("The query was stopped by Ctrl-C before it finished"));
+        }
         Err(error) => {
             return Err(diagnostic_to_labeled_error(
                 &query,
                 Diagnostic::exception(&error),
+                span,
             ));
         }
     };
 
+    let evaluate_duration = evaluate_start.elapsed();
+
     // eprintln!("5");
 
     // Render the result only if they are selected groups not any other statement
+    let render_start = Instant::now();
+    let mut rows_returned = 0;
     let engine_result = engine_results.into_iter().last();
-    let output: Value = if let Some(SelectedGroups(mut groups)) = engine_result {
-        if query_arguments.pagination {
-            apply_pagination(&mut groups, query_arguments.page_size);
-        }
-
-        match query_arguments.output_format {
-            OutputFormatKind::Table => nushell_render::render_objects(&mut groups),
-            OutputFormatKind::JSON => {
-                if let Some(json) = nushell_render::render_groups_to_json(&mut groups) {
-                    Value::test_string(json)
-                } else {
-                    Value::test_string("No JSON data to show".to_string())
+    let output = if let Some(SelectedGroups(mut groups)) = engine_result {
+        let page = query_arguments.pagination.then(|| {
+            apply_pagination(
+                &mut groups,
+                query_arguments.page_size,
+                query_arguments.offset,
+            )
+        });
+        rows_returned = groups.groups.iter().map(|group| group.len()).sum();
+
+        let output = if let Some(column) = query_arguments.nest_by.as_deref() {
+            QueryOutput::Value(render_nested_groups(
+                &mut groups,
+                column,
+                query_arguments.output_format,
+                output_options,
+            )?)
+        } else {
+            match query_arguments.output_format {
+                OutputFormat::Table => QueryOutput::Rows(groups),
+                OutputFormat::Json | OutputFormat::Ndjson | OutputFormat::Yaml
+                    if !output_options.raw =>
+                {
+                    QueryOutput::Value(nushell_render::render_groups_to_typed_records(
+                        &mut groups,
+                        span,
+                    ))
                 }
-            }
-            OutputFormatKind::YAML => {
-                if let Some(yaml) = nushell_render::render_groups_to_yaml(&mut groups) {
-                    Value::test_string(yaml)
-                } else {
-                    Value::test_string("No YAML data to show".to_string())
+                OutputFormat::Csv | OutputFormat::Tsv if !output_options.raw => QueryOutput::Value(
+                    nushell_render::render_groups_to_text_records(&mut groups, span),
+                ),
+                OutputFormat::Json => {
+                    QueryOutput::Value(match nushell_render::render_groups_to_json(&mut groups) {
+                        Some(json) => Value::string(json, span),
+                        None => Value::string("No JSON data to show", span),
+                    })
                 }
-            }
-            OutputFormatKind::CSV => {
-                if let Some(csv) = nushell_render::render_groups_to_csv(&mut groups) {
-                    Value::test_string(csv)
-                } else {
-                    Value::test_string("No CSV data to show".to_string())
+                OutputFormat::Ndjson => {
+                    QueryOutput::Value(match nushell_render::render_groups_to_ndjson(&mut groups) {
+                        Some(ndjson) => Value::string(ndjson, span),
+                        None => Value::string("No NDJSON data to show", span),
+                    })
                 }
+                OutputFormat::Yaml => {
+                    QueryOutput::Value(match nushell_render::render_groups_to_yaml(&mut groups) {
+                        Some(yaml) => Value::string(yaml, span),
+                        None => Value::string("No YAML data to show", span),
+                    })
+                }
+                OutputFormat::Csv => {
+                    QueryOutput::Value(match nushell_render::render_groups_to_csv(&mut groups) {
+                        Some(csv) => Value::string(csv, span),
+                        None => Value::string("No CSV data to show", span),
+                    })
+                }
+                OutputFormat::Tsv => {
+                    QueryOutput::Value(match nushell_render::render_groups_to_tsv(&mut groups) {
+                        Some(tsv) => Value::string(tsv, span),
+                        None => Value::string("No TSV data to show", span),
+                    })
+                }
+                OutputFormat::Markdown => QueryOutput::Value(Value::string(
+                    nushell_render::render_groups_to_markdown(&mut groups),
+                    span,
+                )),
+                OutputFormat::Html => QueryOutput::Value(Value::string(
+                    nushell_render::render_groups_to_html(&mut groups),
+                    span,
+                )),
             }
+        };
+
+        match page {
+            Some(page) => paginated_output(output, &page, span),
+            None => output,
         }
     } else {
         // eprintln!("7");
 
-        Value::test_string("Not a SelectedGroups result".to_string())
+        QueryOutput::Value(Value::string("Not a SelectedGroups result", span))
     };
 
-    let engine_duration = engine_start.elapsed();
-
-    if query_arguments.analysis {
-        eprintln!("\n");
-        eprintln!("Analysis:");
-        eprintln!("Frontend : {:?}", front_duration);
-        eprintln!("Engine   : {:?}", engine_duration);
-        eprintln!("Total    : {:?}", (front_duration + engine_duration));
-        eprintln!("\n");
+    if query_arguments.analysis || repo_errors.is_some() {
+        // The analysis has to time rendering too, and the errors are only
+        // complete once the query has run, so table rows are rendered here
+        // rather than streamed.
+        let result = match output {
+            QueryOutput::Rows(mut groups) => Value::list(
+                nushell_render::render_rows(&mut groups, span).collect(),
+                span,
+            ),
+            QueryOutput::Value(value) => value,
+        };
+        let mut record = nu_protocol::Record::new();
+        record.insert("result", result);
+        if query_arguments.analysis {
+            let analysis = QueryAnalysis {
+                tokenize: tokenize_duration,
+                parse: parse_duration,
+                evaluate: evaluate_duration,
+                render: render_start.elapsed(),
+                provide: provide_timings
+                    .lock()
+                    .map(|timings| timings.clone())
+                    .unwrap_or_default(),
+                rows_returned,
+            };
+            record.insert("analysis", analysis.into_value(span));
+        }
+        if let Some(repo_errors) = repo_errors {
+            let errors = repo_errors
+                .lock()
+                .map(|errors| errors.clone())
+                .unwrap_or_default();
+            record.insert("errors", repo_errors_value(errors, span));
+        }
+        return Ok(QueryOutput::Value(Value::record(record, span)));
     }
 
     Ok(output)
 }
 
-fn apply_pagination(groups: &mut GitQLObject, page_size: usize) {
-    if page_size == 0 {
-        return;
+fn tokenize_gitql_query(query: &str, span: Span) -> Result<Vec<Token>, LabeledError> {
+    let normalized_query = normalize_query(query);
+    let mut tokens = match tokenizer::Tokenizer::tokenize(&normalized_query) {
+        Ok(tokens) => tokens,
+        Err(diagnostic) => {
+            let diagnostic = *diagnostic;
+            return Err(diagnostic_to_labeled_error(query, diagnostic, span));
+        }
+    };
+    if tokens.is_empty() {
+        return Err(LabeledError::new("No tokens to parse").with_label("empty query", span));
     }
+    widen_date_literals(&mut tokens);
+    Ok(tokens)
+}
+
+/// Give `YYYY-MM-DD` literals compared with a `DateTime` column a midnight
+/// time, so `datetime > '2025-01-01'` means `datetime > '2025-01-01 00:00:00'`
+/// instead of failing to type check. GitQL only casts full datetime literals
+/// to `DateTime` on its own.
+fn widen_date_literals(tokens: &mut [Token]) {
+    let types = tables_fields_types();
+    let is_datetime_column = |token: &Token| {
+        matches!(&token.kind, TokenKind::Symbol(name)
+            if types.get(name.as_str()).is_some_and(|data_type| data_type.is_date_time()))
+    };
+    let is_comparison = |token: &Token| {
+        matches!(
+            token.kind,
+            TokenKind::Equal
+                | TokenKind::BangEqual
+                | TokenKind::Greater
+                | TokenKind::GreaterEqual
+                | TokenKind::Less
+                | TokenKind::LessEqual
+        )
+    };
 
-    for group in &mut groups.groups {
-        if group.rows.len() > page_size {
-            group.rows.truncate(page_size);
+    for index in 0..tokens.len() {
+        let TokenKind::String(literal) = &tokens[index].kind else {
+            continue;
+        };
+        if !is_valid_date_format(literal) {
+            continue;
         }
+        let before = |offset: usize| index.checked_sub(offset).map(|index| &tokens[index]);
+        let after = |offset: usize| tokens.get(index + offset);
+        // `datetime > '2025-01-01'` and `'2025-01-01' < datetime`
+        let compared = before(1).is_some_and(is_comparison)
+            && before(2).is_some_and(is_datetime_column)
+            || after(1).is_some_and(is_comparison) && after(2).is_some_and(is_datetime_column);
+        if compared {
+            tokens[index].kind = TokenKind::String(format!("{literal} 00:00:00"));
+        }
+    }
+}
+
+fn parse_gitql_tokens(
+    query: &str,
+    tokens: Vec<Token>,
+    env: &mut Environment,
+    span: Span,
+) -> Result<Vec<Query>, LabeledError> {
+    parser::parse_gql(tokens, env).map_err(|diagnostic| {
+        let diagnostic = *diagnostic;
+        diagnostic_to_labeled_error(query, diagnostic, span)
+    })
+}
+
+/// The query after a leading `EXPLAIN` keyword, if there is one.
+fn strip_explain(query: &str) -> Option<&str> {
+    let query = query.trim_start();
+    let keyword = query.get(.."explain".len())?;
+    let rest = &query["explain".len()..];
+    (keyword.eq_ignore_ascii_case("explain") && rest.starts_with(char::is_whitespace))
+        .then_some(rest)
+}
+
+/// Parse the query after `EXPLAIN` and describe its plan instead of running it.
+fn explain_gitql_query(query: &str, span: Span) -> Result<Value, LabeledError> {
+    let mut env = gitql_environment();
+    let tokens = tokenize_gitql_query(query, span)?;
+    let queries = parse_gitql_tokens(query, tokens, &mut env, span)?;
+    Ok(gitql_explain::explain_queries(&queries, span))
+}
+
+/// Where the time of a query went, returned by `--analysis`.
+struct QueryAnalysis {
+    tokenize: Duration,
+    parse: Duration,
+    /// Engine evaluation, including every `provide` call.
+    evaluate: Duration,
+    render: Duration,
+    provide: Vec<ProvideTiming>,
+    rows_returned: usize,
+}
+
+impl QueryAnalysis {
+    fn into_value(self, span: Span) -> Value {
+        let duration = |duration: Duration| Value::duration(duration.as_nanos() as i64, span);
+        let rows_scanned: usize = self.provide.iter().map(|timing| timing.rows).sum();
+        let provide_per_table = self
+            .provide
+            .into_iter()
+            .map(|timing| {
+                let mut record = nu_protocol::Record::new();
+                record.insert("table", Value::string(timing.table, span));
+                record.insert("repo", Value::string(timing.repo, span));
+                record.insert("rows", Value::int(timing.rows as i64, span));
+                record.insert("duration", duration(timing.duration));
+                Value::record(record, span)
+            })
+            .collect();
+
+        let mut record = nu_protocol::Record::new();
+        record.insert("tokenize", duration(self.tokenize));
+        record.insert("parse", duration(self.parse));
+        record.insert("provide_per_table", Value::list(provide_per_table, span));
+        record.insert("evaluate", duration(self.evaluate));
+        record.insert("render", duration(self.render));
+        record.insert("rows_scanned", Value::int(rows_scanned as i64, span));
+        record.insert("rows_returned", Value::int(self.rows_returned as i64, span));
+        Value::record(record, span)
+    }
+}
+
+/// Render the result rows as a record keyed by each value of `column`.
+fn render_nested_groups(
+    groups: &mut GitQLObject,
+    column: &str,
+    output_format: OutputFormat,
+    output_options: OutputOptions,
+) -> Result<Value, LabeledError> {
+    let span = output_options.span;
+    let nested =
+        match output_format {
+            OutputFormat::Table => nushell_render::render_nested_rows(groups, column, span),
+            OutputFormat::Json | OutputFormat::Yaml if !output_options.raw => {
+                nushell_render::render_nested_typed_records(groups, column, span)
+            }
+            OutputFormat::Json => nushell_render::render_nested_json(groups, column)
+                .map(|json| Value::string(json, span)),
+            OutputFormat::Yaml => nushell_render::render_nested_yaml(groups, column)
+                .map(|yaml| Value::string(yaml, span)),
+            _ => {
+                return Err(LabeledError::new("--nest-by needs a nested output format")
+                    .with_label("flat output format", span)
+                    .with_help("Use --output table, json or yaml with --nest-by"));
+            }
+        };
+    nested.map_err(|error| LabeledError::new("Unknown --nest-by column").with_label(error, span))
+}
+
+/// Position of a page within the full result.
+struct Page {
+    number: usize,
+    pages: usize,
+    total: usize,
+}
+
+/// Rows skipped before the 1-based `page`, or `None` when that doesn't fit
+/// in a `usize`.
+fn page_start(page: i64, page_size: usize) -> Option<usize> {
+    usize::try_from(page - 1).ok()?.checked_mul(page_size)
+}
+
+/// Keep the `page_size` rows that start `offset` rows into the result.
+fn apply_pagination(groups: &mut GitQLObject, page_size: usize, offset: usize) -> Page {
+    if groups.len() > 1 {
+        groups.flat();
+    }
+
+    let total = groups.groups.first().map_or(0, |group| group.len());
+    if let Some(group) = groups.groups.first_mut() {
+        group.rows.drain(..offset.min(total));
+        group.rows.truncate(page_size);
+    }
+
+    Page {
+        number: offset / page_size + 1,
+        pages: total.div_ceil(page_size),
+        total,
     }
 }
 
+/// Wrap a page of output in a `{page, pages, total, rows}` record, so
+/// scripts can tell how many pages are left.
+fn paginated_output(output: QueryOutput, page: &Page, span: Span) -> QueryOutput {
+    let rows = match output {
+        QueryOutput::Rows(mut groups) => Value::list(
+            nushell_render::render_rows(&mut groups, span).collect(),
+            span,
+        ),
+        QueryOutput::Value(value) => value,
+    };
+
+    let mut record = nu_protocol::Record::new();
+    record.insert("page", Value::int(page.number as i64, span));
+    record.insert("pages", Value::int(page.pages as i64, span));
+    record.insert("total", Value::int(page.total as i64, span));
+    record.insert("rows", rows);
+    QueryOutput::Value(Value::record(record, span))
+}
+
 fn normalize_query(query: &str) -> String {
     let mut normalized = String::with_capacity(query.len());
     let lower_query = query.to_lowercase();
@@ -366,7 +1043,12 @@ fn normalize_query(query: &str) -> String {
     normalized
 }
 
-fn render_schema_query(query: &str, output_format: &OutputFormatKind) -> Option<Value> {
+fn render_schema_query(
+    query: &str,
+    output_format: &OutputFormat,
+    output_options: OutputOptions,
+) -> Option<Value> {
+    let span = output_options.span;
     let normalized = query.trim();
     let rows: Option<Vec<Value>> = if normalized.eq_ignore_ascii_case("show tables") {
         Some(
@@ -374,8 +1056,8 @@ fn render_schema_query(query: &str, output_format: &OutputFormatKind) -> Option<
                 .into_iter()
                 .map(|table| {
                     let mut record = nu_protocol::Record::new();
-                    record.insert("table", Value::test_string(table.to_string()));
-                    Value::test_record(record)
+                    record.insert("table", Value::string(table, span));
+                    Value::record(record, span)
                 })
                 .collect(),
         )
@@ -388,9 +1070,9 @@ fn render_schema_query(query: &str, output_format: &OutputFormatKind) -> Option<
                     .into_iter()
                     .map(|(name, type_name)| {
                         let mut record = nu_protocol::Record::new();
-                        record.insert("field", Value::test_string(name.to_string()));
-                        record.insert("type", Value::test_string(type_name));
-                        Value::test_record(record)
+                        record.insert("field", Value::string(name, span));
+                        record.insert("type", Value::string(type_name, span));
+                        Value::record(record, span)
                     })
                     .collect()
             })
@@ -400,12 +1082,30 @@ fn render_schema_query(query: &str, output_format: &OutputFormatKind) -> Option<
     };
 
     let rows = rows?;
-    match output_format {
-        OutputFormatKind::Table => Some(Value::test_list(rows)),
-        OutputFormatKind::JSON => render_value_list_to_json(&rows).map(Value::test_string),
-        OutputFormatKind::CSV => render_value_list_to_csv(&rows).map(Value::test_string),
-        OutputFormatKind::YAML => render_value_list_to_yaml(&rows).map(Value::test_string),
+    // Schema rows are all text, so every structured format has the same
+    // Nushell equivalent as the table.
+    if *output_format == OutputFormat::Table
+        || (output_format.is_structured() && !output_options.raw)
+    {
+        return Some(Value::list(rows, span));
     }
+    let text = match output_format {
+        OutputFormat::Table => None,
+        OutputFormat::Json => render_value_list_to_json(&rows),
+        OutputFormat::Ndjson => render_value_list_to_ndjson(&rows),
+        OutputFormat::Csv => value_list_cells(&rows).and_then(|(titles, cells)| {
+            nushell_render::render_cells_to_delimited(&titles, &cells, b',')
+        }),
+        OutputFormat::Tsv => value_list_cells(&rows).and_then(|(titles, cells)| {
+            nushell_render::render_cells_to_delimited(&titles, &cells, b'\t')
+        }),
+        OutputFormat::Yaml => render_value_list_to_yaml(&rows),
+        OutputFormat::Markdown => value_list_cells(&rows)
+            .map(|(titles, cells)| nushell_render::render_cells_to_markdown(&titles, &cells)),
+        OutputFormat::Html => value_list_cells(&rows)
+            .map(|(titles, cells)| nushell_render::render_cells_to_html(&titles, &cells)),
+    };
+    text.map(|text| Value::string(text, span))
 }
 
 fn render_value_list_to_yaml(rows: &[Value]) -> Option<String> {
@@ -417,7 +1117,7 @@ fn render_value_list_to_yaml(rows: &[Value]) -> Option<String> {
         for (name, value) in record.iter() {
             object.insert(
                 serde_yaml::Value::String(name.clone()),
-                serde_yaml::Value::String(value.clone().coerce_into_string().unwrap_or_default()),
+                serde_yaml::to_value(nu_value_to_json(value)).unwrap_or(serde_yaml::Value::Null),
             );
         }
         elements.push(serde_yaml::Value::Mapping(object));
@@ -433,8 +1133,7 @@ fn render_value_list_to_json(rows: &[Value]) -> Option<String> {
         let record = row.as_record().ok()?;
         let mut object = serde_json::Map::new();
         for (name, value) in record.iter() {
-            let string_value = value.clone().coerce_into_string().unwrap_or_default();
-            object.insert(name.clone(), serde_json::Value::String(string_value));
+            object.insert(name.clone(), nu_value_to_json(value));
         }
         elements.push(serde_json::Value::Object(object));
     }
@@ -442,38 +1141,71 @@ fn render_value_list_to_json(rows: &[Value]) -> Option<String> {
     serde_json::to_string(&serde_json::Value::Array(elements)).ok()
 }
 
-fn render_value_list_to_csv(rows: &[Value]) -> Option<String> {
+/// Convert a Nushell value to its native JSON type, with dates as RFC 3339.
+fn nu_value_to_json(value: &Value) -> serde_json::Value {
+    match value {
+        Value::Nothing { .. } => serde_json::Value::Null,
+        Value::Bool { val, .. } => serde_json::Value::Bool(*val),
+        Value::Int { val, .. } => serde_json::Value::from(*val),
+        Value::Float { val, .. } => serde_json::Number::from_f64(*val)
+            .map_or(serde_json::Value::Null, serde_json::Value::Number),
+        Value::Date { val, .. } => serde_json::Value::String(val.to_rfc3339()),
+        Value::List { vals, .. } => {
+            serde_json::Value::Array(vals.iter().map(nu_value_to_json).collect())
+        }
+        Value::Record { val, .. } => serde_json::Value::Object(
+            val.iter()
+                .map(|(name, value)| (name.clone(), nu_value_to_json(value)))
+                .collect(),
+        ),
+        _ => serde_json::Value::String(value.clone().coerce_into_string().unwrap_or_default()),
+    }
+}
+
+fn render_value_list_to_ndjson(rows: &[Value]) -> Option<String> {
+    let mut lines = String::new();
+
+    for row in rows {
+        row.as_record().ok()?;
+        lines.push_str(&serde_json::to_string(&nu_value_to_json(row)).ok()?);
+        lines.push('\n');
+    }
+
+    Some(lines)
+}
+
+/// Column titles taken from the first record, and the text of every cell.
+fn value_list_cells(rows: &[Value]) -> Option<(Vec<String>, Vec<Vec<String>>)> {
     let first_row = rows.first()?;
     let first_record = first_row.as_record().ok()?;
     let headers: Vec<String> = first_record.iter().map(|(name, _)| name.clone()).collect();
 
-    let mut writer = csv::Writer::from_writer(vec![]);
-    writer.write_record(&headers).ok()?;
-
+    let mut cells = Vec::with_capacity(rows.len());
     for row in rows {
         let record = row.as_record().ok()?;
         let values: Vec<String> = record
             .iter()
             .map(|(_, value)| value.clone().coerce_into_string().unwrap_or_default())
             .collect();
-        writer.write_record(values).ok()?;
+        cells.push(values);
     }
 
-    writer
-        .into_inner()
-        .ok()
-        .and_then(|writer_content| String::from_utf8(writer_content).ok())
+    Some((headers, cells))
 }
 
-fn diagnostic_to_labeled_error(_query: &str, diagnostic: Diagnostic) -> LabeledError {
+fn diagnostic_to_labeled_error(_query: &str, diagnostic: Diagnostic, span: Span) -> LabeledError {
     let mut error = LabeledError::new(diagnostic.message().to_string());
-    if let Some(location) = diagnostic.location() {
-        let span = Span::new(
-            location.column_start.saturating_sub(1) as usize,
-            location.column_end as usize,
-        );
-        error = error.with_label(diagnostic.label().to_string(), span);
-    }
+    // Query locations are columns inside the query string, not offsets into
+    // Nushell's source, so label the call itself and name the column.
+    let label = match (diagnostic.label(), diagnostic.location()) {
+        (label, Some(location)) if !label.is_empty() => {
+            format!("{label} (column {})", location.column_start)
+        }
+        (label, _) if !label.is_empty() => label.to_string(),
+        (_, Some(location)) => format!("at column {}", location.column_start),
+        _ => "while running this query".to_string(),
+    };
+    error = error.with_label(label, span);
     if !diagnostic.helps().is_empty() {
         error = error.with_help(diagnostic.helps().join(" "));
     }
@@ -523,13 +1255,48 @@ fn parse_repo_paths(
     Ok(vec![current_dir.to_string()])
 }
 
-/// Resolve the output format name into a `OutputFormatKind`.
-fn resolve_output_format(output_flag: Option<String>) -> OutputFormatKind {
+/// Paths of the repositories found under `dir` for `--discover`.
+fn discover_repo_paths(
+    current_dir: &str,
+    dir: &str,
+    max_depth: Option<usize>,
+    include_submodules: bool,
+    span: Span,
+) -> Result<Vec<String>, LabeledError> {
+    let root = resolve_repo_path(dir, current_dir);
+    let repositories =
+        repo_discovery::discover_repositories(Path::new(&root), max_depth, include_submodules)
+            .map_err(|error| {
+                LabeledError::new("Can't discover repositories").with_label(error, span)
+            })?;
+    if repositories.is_empty() {
+        return Err(LabeledError::new("No git repositories found")
+            .with_label(format!("nothing found under {root}"), span));
+    }
+
+    Ok(repositories
+        .into_iter()
+        .map(|path| path.to_string_lossy().into_owned())
+        .collect())
+}
+
+/// Whether a revision spec names a range (`a..b`, `a...b`, `..b`) rather
+/// than a single revision.
+fn is_revision_range(spec: &str) -> bool {
+    spec.contains("..")
+}
+
+/// Resolve the output format name into an `OutputFormat`.
+fn resolve_output_format(output_flag: Option<String>) -> OutputFormat {
     match output_flag.as_deref().map(str::to_lowercase).as_deref() {
-        Some("json") => OutputFormatKind::JSON,
-        Some("csv") => OutputFormatKind::CSV,
-        Some("yaml") | Some("yml") => OutputFormatKind::YAML,
-        _ => OutputFormatKind::Table,
+        Some("json") => OutputFormat::Json,
+        Some("ndjson") | Some("jsonl") => OutputFormat::Ndjson,
+        Some("csv") => OutputFormat::Csv,
+        Some("tsv") => OutputFormat::Tsv,
+        Some("yaml") | Some("yml") => OutputFormat::Yaml,
+        Some("markdown") | Some("md") => OutputFormat::Markdown,
+        Some("html") => OutputFormat::Html,
+        _ => OutputFormat::Table,
     }
 }
 
@@ -540,6 +1307,43 @@ fn validate_git_repositories(repositories: &[String]) -> Result<Vec<gix::Reposit
         .collect()
 }
 
+/// Open every repository that can be opened, and the errors of those that
+/// can't, for `--keep-going`.
+fn open_git_repositories(repositories: &[String]) -> (Vec<gix::Repository>, Vec<RepoError>) {
+    let mut repos = vec![];
+    let mut errors = vec![];
+    for repository in repositories {
+        match gix::open(repository) {
+            Ok(repo) => repos.push(repo),
+            Err(err) => errors.push(RepoError {
+                repo: repository.clone(),
+                table: None,
+                error: err.to_string(),
+            }),
+        }
+    }
+    (repos, errors)
+}
+
+fn repo_errors_value(errors: Vec<RepoError>, span: Span) -> Value {
+    let errors = errors
+        .into_iter()
+        .map(|error| {
+            let mut record = nu_protocol::Record::new();
+            record.insert("repo", Value::string(error.repo, span));
+            record.insert(
+                "table",
+                error
+                    .table
+                    .map_or(Value::nothing(span), |table| Value::string(table, span)),
+            );
+            record.insert("error", Value::string(error.error, span));
+            Value::record(record, span)
+        })
+        .collect();
+    Value::list(errors, span)
+}
+
 #[test]
 fn test_examples() -> Result<(), nu_protocol::ShellError> {
     use nu_plugin_test_support::PluginTest;
@@ -553,14 +1357,24 @@ fn test_examples() -> Result<(), nu_protocol::ShellError> {
 
 #[test]
 fn test_show_tables_schema_query() {
-    let value = render_schema_query("show tables", &OutputFormatKind::Table).unwrap();
+    let value = render_schema_query(
+        "show tables",
+        &OutputFormat::Table,
+        test_output_options(false),
+    )
+    .unwrap();
     let list = value.as_list().expect("expected list");
     assert!(!list.is_empty());
 }
 
 #[test]
 fn test_describe_commits_schema_query() {
-    let value = render_schema_query("describe commits", &OutputFormatKind::Table).unwrap();
+    let value = render_schema_query(
+        "describe commits",
+        &OutputFormat::Table,
+        test_output_options(false),
+    )
+    .unwrap();
     let list = value.as_list().expect("expected list");
     assert!(list.iter().any(|row| {
         row.as_record()
@@ -573,7 +1387,12 @@ fn test_describe_commits_schema_query() {
 
 #[test]
 fn test_describe_commits_schema_query_includes_repo_name() {
-    let value = render_schema_query("describe commits", &OutputFormatKind::Table).unwrap();
+    let value = render_schema_query(
+        "describe commits",
+        &OutputFormat::Table,
+        test_output_options(false),
+    )
+    .unwrap();
     let list = value.as_list().expect("expected list");
     assert!(list.iter().any(|row| {
         row.as_record()
@@ -586,44 +1405,339 @@ fn test_describe_commits_schema_query_includes_repo_name() {
 
 #[test]
 fn test_describe_commits_yaml_schema_query() {
-    let yaml = render_schema_query("describe commits", &OutputFormatKind::YAML)
-        .unwrap()
-        .coerce_into_string()
-        .unwrap();
+    let yaml = render_schema_query(
+        "describe commits",
+        &OutputFormat::Yaml,
+        test_output_options(true),
+    )
+    .unwrap()
+    .coerce_into_string()
+    .unwrap();
     assert!(yaml.contains("repo_name"));
 }
 
-#[cfg(test)]
-mod regression_tests {
-    use super::*;
-    use std::fs;
-    use std::process::Command;
-    use tempfile::TempDir;
+#[test]
+fn test_describe_commits_yaml_schema_query_returns_records_without_raw() {
+    let value = render_schema_query(
+        "describe commits",
+        &OutputFormat::Yaml,
+        test_output_options(false),
+    )
+    .expect("schema value");
+    let list = value.as_list().expect("expected list");
+    assert!(list.iter().all(|row| row.as_record().is_ok()));
+    assert_eq!(value.span(), Span::test_data());
+}
 
-    fn init_temp_repo() -> TempDir {
-        let temp_dir = TempDir::new().expect("create temp dir");
-        let repo_path = temp_dir.path();
+#[test]
+fn test_show_tables_text_formats() {
+    let render = |format| {
+        render_schema_query("show tables", &format, test_output_options(true))
+            .expect("schema value")
+            .coerce_into_string()
+            .expect("text output")
+    };
 
-        let run = |args: &[&str]| {
-            let status = Command::new("git")
-                .args(args)
-                .current_dir(repo_path)
-                .status()
-                .expect("git command failed");
-            assert!(status.success(), "git {:?} failed", args);
-        };
+    let markdown = render(OutputFormat::Markdown);
+    assert!(markdown.starts_with("| table |\n| --- |\n"));
+    assert!(markdown.contains("| commits |\n"));
 
-        run(&["init"]);
-        run(&["config", "user.name", "Test User"]);
-        run(&["config", "user.email", "test@example.com"]);
-        fs::write(repo_path.join("README.md"), "test repo").expect("write file");
-        run(&["add", "README.md"]);
-        run(&["commit", "-m", "initial commit"]);
+    let html = render(OutputFormat::Html);
+    assert!(html.contains("<th>table</th>"));
+    assert!(html.contains("<td>commits</td>"));
 
-        temp_dir
-    }
+    let tsv = render(OutputFormat::Tsv);
+    assert!(tsv.starts_with("table\n"));
 
-    #[test]
+    let ndjson = render(OutputFormat::Ndjson);
+    assert!(
+        ndjson
+            .lines()
+            .all(|line| serde_json::from_str::<serde_json::Value>(line).is_ok())
+    );
+    assert!(ndjson.contains(r#"{"table":"commits"}"#));
+}
+
+#[test]
+fn test_every_output_format_renders_all_groups() {
+    use gitql_core::object::{Group, Row};
+    use gitql_core::values::text::TextValue;
+
+    let grouped = || GitQLObject {
+        titles: vec!["name".to_string()],
+        groups: ["first", "second"]
+            .into_iter()
+            .map(|name| Group {
+                rows: vec![Row {
+                    values: vec![Box::new(TextValue::new(name.to_string()))],
+                }],
+            })
+            .collect(),
+    };
+
+    let csv = nushell_render::render_groups_to_csv(&mut grouped()).expect("csv");
+    assert_eq!(csv, "name\nfirst\nsecond\n");
+    let json = nushell_render::render_groups_to_json(&mut grouped()).expect("json");
+    assert_eq!(json, r#"[{"name":"first"},{"name":"second"}]"#);
+    let yaml = nushell_render::render_groups_to_yaml(&mut grouped()).expect("yaml");
+    assert!(yaml.contains("first") && yaml.contains("second"));
+    let markdown = nushell_render::render_groups_to_markdown(&mut grouped());
+    assert!(markdown.ends_with("| first |\n| second |\n"));
+    let records = nushell_render::render_groups_to_typed_records(&mut grouped(), Span::test_data());
+    assert_eq!(records.as_list().map(|rows| rows.len()).ok(), Some(2));
+}
+
+#[test]
+fn test_structured_records_keep_dates_and_empty_nulls() {
+    use gitql_core::object::{Group, Row};
+    use gitql_core::values::{datetime::DateTimeValue, null::NullValue};
+
+    let result = || GitQLObject {
+        titles: vec!["datetime".to_string(), "upstream".to_string()],
+        groups: vec![Group {
+            rows: vec![Row {
+                values: vec![
+                    Box::new(DateTimeValue {
+                        value: 1_700_000_000,
+                    }),
+                    Box::new(NullValue),
+                ],
+            }],
+        }],
+    };
+
+    let records = nushell_render::render_groups_to_typed_records(&mut result(), Span::test_data());
+    let row = &records.as_list().expect("rows")[0];
+    let datetime = row.get_data_by_key("datetime").expect("datetime");
+    assert_eq!(
+        datetime.as_date().map(|date| date.timestamp()).ok(),
+        Some(1_700_000_000)
+    );
+    assert!(
+        row.get_data_by_key("upstream")
+            .expect("upstream")
+            .is_nothing()
+    );
+
+    let records = nushell_render::render_groups_to_text_records(&mut result(), Span::test_data());
+    let row = &records.as_list().expect("rows")[0];
+    assert_eq!(
+        row.get_data_by_key("upstream")
+            .expect("upstream")
+            .as_str()
+            .ok(),
+        Some("")
+    );
+    let csv = nushell_render::render_groups_to_csv(&mut result()).expect("csv");
+    assert!(csv.ends_with(",\n"));
+}
+
+#[test]
+fn test_explain_describes_plan_without_running_query() {
+    let query =
+        strip_explain("EXPLAIN select commit_id, insertions from diffs where name = 'me' limit 5")
+            .expect("explain query");
+    let plans = explain_gitql_query(query, Span::test_data()).expect("explain");
+    let plans = plans.as_list().expect("plans");
+    let plan = plans[0].as_record().expect("plan");
+
+    let strings = |column| -> Vec<String> {
+        plan.get(column)
+            .and_then(|value| value.as_list().ok())
+            .expect("list column")
+            .iter()
+            .filter_map(|value| value.as_str().ok().map(str::to_string))
+            .collect()
+    };
+    assert_eq!(strings("tables"), ["diffs"]);
+    assert_eq!(strings("selected_columns"), ["commit_id", "insertions"]);
+
+    let filters = plan.get("filters").and_then(|value| value.as_list().ok());
+    let condition = filters
+        .and_then(|filters| filters.first())
+        .and_then(|filter| filter.get_data_by_key("condition"))
+        .expect("where condition");
+    let field = |value: &Value, key| {
+        value
+            .get_data_by_key(key)
+            .and_then(|value| value.as_str().ok().map(str::to_string))
+    };
+    assert_eq!(field(&condition, "kind").as_deref(), Some("comparison"));
+    assert_eq!(field(&condition, "operator").as_deref(), Some("="));
+    let left = condition.get_data_by_key("left").expect("left operand");
+    assert_eq!(field(&left, "name").as_deref(), Some("name"));
+
+    let expensive = plan
+        .get("expensive_columns")
+        .and_then(|value| value.as_list().ok());
+    let expensive: Vec<String> = expensive
+        .expect("expensive columns")
+        .iter()
+        .filter_map(|value| field(value, "column"))
+        .collect();
+    assert_eq!(expensive, ["insertions"]);
+
+    let statements: Vec<String> = plan
+        .get("statements")
+        .and_then(|value| value.as_list().ok())
+        .expect("statements")
+        .iter()
+        .filter_map(|value| field(value, "statement"))
+        .collect();
+    assert_eq!(statements, ["select", "where", "limit"]);
+
+    let plans = explain_gitql_query(
+        "select title from commits limit 2 into outfile 'titles.txt'",
+        Span::test_data(),
+    )
+    .expect("explain into");
+    let plans = plans.as_list().expect("plans");
+    let steps = plans[0]
+        .get_data_by_key("statements")
+        .and_then(|value| value.as_list().ok().map(<[Value]>::to_vec))
+        .expect("statements");
+    let into = steps.last().expect("into step");
+    assert_eq!(field(into, "statement").as_deref(), Some("into"));
+    assert_eq!(field(into, "file_path").as_deref(), Some("titles.txt"));
+
+    assert!(strip_explain("select * from commits").is_none());
+    assert!(strip_explain("explainer").is_none());
+}
+
+#[test]
+fn test_page_start_rejects_pages_past_the_last_row() {
+    assert_eq!(page_start(1, 50), Some(0));
+    assert_eq!(page_start(3, 50), Some(100));
+    assert_eq!(page_start(i64::MAX, 50), None);
+}
+
+#[test]
+fn test_revision_ranges_are_told_apart_from_single_revisions() {
+    for range in ["v1.0..v2.0", "main...feature", "..HEAD", "HEAD~3.."] {
+        assert!(is_revision_range(range), "{range} is a range");
+    }
+    for revision in ["HEAD", "HEAD~3", "v1.0", "main@{1}", "1a2b3c4"] {
+        assert!(
+            !is_revision_range(revision),
+            "{revision} is a single revision"
+        );
+    }
+}
+
+#[test]
+fn test_commit_filter_pushes_simple_bounds_and_limit() {
+    let commit_filter = |query: &str| {
+        let mut env = gitql_environment();
+        let tokens = tokenize_gitql_query(query, Span::test_data()).expect("tokenize");
+        let queries =
+            parse_gitql_tokens(query, tokens, &mut env, Span::test_data()).expect("parse");
+        CommitFilter::from_queries(&queries)
+    };
+
+    let filter = commit_filter(
+        "select title from commits where datetime > '2024-06-01 00:00:00' \
+         and '2024-07-01 00:00:00' >= datetime and author_email = 'a@example.com' limit 5 offset 2",
+    )
+    .expect("pushdown");
+    assert_eq!(filter.table, "commits");
+    assert_eq!(filter.since, Some(1_717_200_001));
+    assert_eq!(filter.until, Some(1_719_792_000));
+    assert_eq!(filter.author_email.as_deref(), Some("a@example.com"));
+    assert_eq!(filter.limit, Some(7));
+
+    let filter = commit_filter("select commit_id from diffs where email = 'a@example.com' limit 3")
+        .expect("pushdown");
+    assert_eq!(filter.author_email.as_deref(), Some("a@example.com"));
+    assert_eq!(filter.limit, Some(3));
+
+    // A condition the walk can't apply leaves rows for the engine to drop,
+    // so the walk must not stop at the limit.
+    let filter = commit_filter(
+        "select title from commits where datetime >= '2024-06-01 00:00:00' \
+         and title like '%fix%' limit 5",
+    )
+    .expect("pushdown");
+    assert_eq!(filter.since, Some(1_717_200_000));
+    assert_eq!(filter.limit, None);
+
+    // Date-only literals compared with `datetime` mean midnight UTC.
+    let filter = commit_filter(
+        "select title from commits where datetime > '2024-06-01' and '2024-07-01' >= datetime",
+    )
+    .expect("pushdown");
+    assert_eq!(filter.since, Some(1_717_200_001));
+    assert_eq!(filter.until, Some(1_719_792_000));
+
+    let filter = commit_filter("select title from commits order by title limit 5");
+    assert_eq!(filter, None);
+    assert_eq!(commit_filter("select name from branches limit 5"), None);
+}
+
+#[cfg(test)]
+fn test_output_options(raw: bool) -> OutputOptions {
+    OutputOptions {
+        span: Span::test_data(),
+        raw,
+    }
+}
+
+#[cfg(test)]
+mod regression_tests {
+    use super::*;
+    use nu_protocol::Signals;
+    use std::fs;
+    use std::process::Command;
+    use std::sync::Arc;
+    use std::sync::atomic::AtomicBool;
+    use tempfile::TempDir;
+
+    fn git(repo_path: &Path, args: &[&str]) {
+        let status = Command::new("git")
+            .args(args)
+            .current_dir(repo_path)
+            .status()
+            .expect("git command failed");
+        assert!(status.success(), "git {:?} failed", args);
+    }
+
+    fn init_temp_repo() -> TempDir {
+        let temp_dir = TempDir::new().expect("create temp dir");
+        let repo_path = temp_dir.path();
+
+        git(repo_path, &["init"]);
+        git(repo_path, &["config", "user.name", "Test User"]);
+        git(repo_path, &["config", "user.email", "test@example.com"]);
+        fs::write(repo_path.join("README.md"), "test repo").expect("write file");
+        git(repo_path, &["add", "README.md"]);
+        git(repo_path, &["commit", "-m", "initial commit"]);
+
+        temp_dir
+    }
+
+    fn commit_file(
+        repo_path: &Path,
+        file: &str,
+        contents: &str,
+        message: &str,
+        date: Option<&str>,
+    ) {
+        fs::write(repo_path.join(file), contents).expect("write file");
+        git(repo_path, &["add", file]);
+        let mut command = Command::new("git");
+        if let Some(date) = date {
+            command
+                .env("GIT_AUTHOR_DATE", date)
+                .env("GIT_COMMITTER_DATE", date);
+        }
+        let status = command
+            .args(["commit", "-m", message])
+            .current_dir(repo_path)
+            .status()
+            .expect("git command failed");
+        assert!(status.success(), "git commit failed");
+    }
+
+    #[test]
     fn test_git_data_provider_includes_repo_name() {
         let repo_dir = init_temp_repo();
         let repo_path = repo_dir.path();
@@ -680,42 +1794,1290 @@ mod regression_tests {
     }
 
     #[test]
-    fn test_select_count_with_analysis_returns_value() {
+    fn test_git_data_provider_diff_files_returns_one_row_per_path() {
+        let repo_dir = init_temp_repo();
+        let repo = gix::open(repo_dir.path()).expect("open repo");
+        let provider = GitDataProvider::new(vec![repo]);
+        let rows = provider
+            .provide(
+                "diff_files",
+                &[
+                    "path".to_string(),
+                    "change_type".to_string(),
+                    "insertions".to_string(),
+                    "is_binary".to_string(),
+                ],
+            )
+            .expect("provide diff_files");
+
+        assert_eq!(rows.len(), 1);
+        let row = &rows[0];
+        assert_eq!(row.values[0].as_text().as_deref(), Some("README.md"));
+        assert_eq!(row.values[1].as_text().as_deref(), Some("added"));
+        assert_eq!(row.values[2].as_int(), Some(1));
+        assert_eq!(row.values[3].as_bool(), Some(false));
+    }
+
+    #[test]
+    fn test_git_data_provider_commits_honor_revision_range() {
+        let repo_dir = init_temp_repo();
+        commit_file(repo_dir.path(), "CHANGELOG.md", "v2", "second commit", None);
+
+        let repo = gix::open(repo_dir.path()).expect("open repo");
+        let provider =
+            GitDataProvider::new(vec![repo]).with_revision(Some("HEAD~1..HEAD".to_string()));
+        let rows = provider
+            .provide("commits", &["title".to_string()])
+            .expect("provide commits");
+
+        assert_eq!(rows.len(), 1);
+        assert_eq!(
+            rows[0].values[0].as_text().as_deref(),
+            Some("second commit")
+        );
+
+        let provider = provider.with_revision(Some("HEAD~1".to_string()));
+        let rows = provider
+            .provide("commits", &["title".to_string()])
+            .expect("provide commits");
+
+        assert_eq!(rows.len(), 1);
+        assert_eq!(
+            rows[0].values[0].as_text().as_deref(),
+            Some("initial commit")
+        );
+    }
+
+    #[test]
+    fn test_git_data_provider_blame_returns_one_row_per_line() {
+        let repo_dir = init_temp_repo();
+        commit_file(
+            repo_dir.path(),
+            "README.md",
+            "test repo\nsecond line\n",
+            "add second line",
+            None,
+        );
+
+        let repo = gix::open(repo_dir.path()).expect("open repo");
+        let provider = GitDataProvider::new(vec![repo]).with_path(Some("README.md".to_string()));
+        let rows = provider
+            .provide(
+                "blame",
+                &[
+                    "line_number".to_string(),
+                    "author_name".to_string(),
+                    "content".to_string(),
+                ],
+            )
+            .expect("provide blame");
+
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[1].values[0].as_int(), Some(2));
+        assert_eq!(rows[1].values[1].as_text().as_deref(), Some("Test User"));
+        assert_eq!(rows[1].values[2].as_text().as_deref(), Some("second line"));
+    }
+
+    #[test]
+    fn test_git_data_provider_blame_requires_path() {
+        let repo_dir = init_temp_repo();
+        let repo = gix::open(repo_dir.path()).expect("open repo");
+        let provider = GitDataProvider::new(vec![repo]);
+
+        assert!(provider.provide("blame", &["content".to_string()]).is_err());
+    }
+
+    #[test]
+    fn test_interrupted_query_returns_clean_error() {
         let repo_dir = init_temp_repo();
         let repo_path = repo_dir.path().to_string_lossy().to_string();
         let repo = gix::open(&repo_path).expect("open repo");
+        let signals = Signals::new(Arc::new(AtomicBool::new(true)));
 
-        let query_arguments = Arguments {
+        let query_arguments = QueryArguments {
             repos: vec![repo_path],
-            output_format: OutputFormatKind::Table,
+            output_format: OutputFormat::Table,
             pagination: false,
             page_size: 10,
-            analysis: true,
-            enable_line_editor: false,
+            offset: 0,
+            analysis: false,
+            nest_by: None,
+        };
+        let mut env = gitql_environment();
+
+        for query in [
+            "select * from commits",
+            "select * from diffs",
+            "select * from branches",
+        ] {
+            let result = execute_gitql_query(
+                query.to_string(),
+                &query_arguments,
+                GitDataProvider::new(vec![repo.clone()]).with_signals(signals.clone()),
+                &mut env,
+                test_output_options(false),
+            );
+            let Err(error) = result else {
+                panic!("expected `{query}` to be interrupted");
+            };
+            assert_eq!(error.msg, "Query interrupted");
+        }
+    }
+
+    #[test]
+    fn test_git_data_provider_files_lists_tree_entries() {
+        let repo_dir = init_temp_repo();
+        fs::create_dir(repo_dir.path().join("src")).expect("create dir");
+        commit_file(repo_dir.path(), "src/lib.rs", "// lib\n", "add lib", None);
+
+        let repo = gix::open(repo_dir.path()).expect("open repo");
+        let provider = GitDataProvider::new(vec![repo]);
+        let rows = provider
+            .provide(
+                "files",
+                &[
+                    "path".to_string(),
+                    "extension".to_string(),
+                    "size".to_string(),
+                    "depth".to_string(),
+                ],
+            )
+            .expect("provide files");
+
+        assert_eq!(rows.len(), 2);
+        let lib = rows
+            .iter()
+            .find(|row| row.values[0].as_text().as_deref() == Some("src/lib.rs"))
+            .expect("src/lib.rs is listed");
+        assert_eq!(lib.values[1].as_text().as_deref(), Some("rs"));
+        assert_eq!(lib.values[2].as_int(), Some(7));
+        assert_eq!(lib.values[3].as_int(), Some(1));
+    }
+
+    #[test]
+    fn test_git_data_provider_tags_expose_annotation_metadata() {
+        let repo_dir = init_temp_repo();
+        git(
+            repo_dir.path(),
+            &["tag", "-a", "v1.0.0", "-m", "release 1.0.0"],
+        );
+        git(repo_dir.path(), &["tag", "lightweight"]);
+
+        let repo = gix::open(repo_dir.path()).expect("open repo");
+        let provider = GitDataProvider::new(vec![repo]);
+        let rows = provider
+            .provide(
+                "tags",
+                &[
+                    "name".to_string(),
+                    "is_annotated".to_string(),
+                    "tagger_name".to_string(),
+                    "title".to_string(),
+                    "target_type".to_string(),
+                    "commit_datetime".to_string(),
+                ],
+            )
+            .expect("provide tags");
+
+        let tag = |name: &str| {
+            rows.iter()
+                .find(|row| row.values[0].as_text().as_deref() == Some(name))
+                .expect("tag is listed")
+        };
+        let annotated = tag("v1.0.0");
+        assert_eq!(annotated.values[1].as_bool(), Some(true));
+        assert_eq!(annotated.values[2].as_text().as_deref(), Some("Test User"));
+        assert_eq!(
+            annotated.values[3].as_text().as_deref(),
+            Some("release 1.0.0")
+        );
+        assert_eq!(annotated.values[4].as_text().as_deref(), Some("commit"));
+        assert!(annotated.values[5].is_date_time());
+
+        let lightweight = tag("lightweight");
+        assert_eq!(lightweight.values[1].as_bool(), Some(false));
+        assert!(lightweight.values[2].is_null());
+        assert!(lightweight.values[5].is_date_time());
+    }
+
+    #[test]
+    fn test_tags_can_be_filtered_by_datetime() {
+        let repo_dir = init_temp_repo();
+        git(
+            repo_dir.path(),
+            &["tag", "-a", "v1.0.0", "-m", "release 1.0.0"],
+        );
+        let repo_path = repo_dir.path().to_string_lossy().to_string();
+        let repo = gix::open(&repo_path).expect("open repo");
+        let query_arguments = QueryArguments {
+            repos: vec![repo_path],
+            output_format: OutputFormat::Json,
+            pagination: false,
+            page_size: 10,
+            offset: 0,
+            analysis: false,
+            nest_by: None,
+        };
+
+        let names = |query: &str| -> Vec<String> {
+            let output = execute_gitql_query(
+                query.to_string(),
+                &query_arguments,
+                GitDataProvider::new(vec![repo.clone()]),
+                &mut gitql_environment(),
+                test_output_options(true),
+            )
+            .expect("execute query");
+            let QueryOutput::Value(value) = output else {
+                panic!("expected serialized output");
+            };
+            let json: serde_json::Value =
+                serde_json::from_str(value.as_str().expect("json text")).expect("parse json");
+            json.as_array()
+                .expect("rows")
+                .iter()
+                .filter_map(|row| row["name"].as_str().map(str::to_string))
+                .collect()
+        };
+
+        assert_eq!(
+            names("select name from tags where tagger_datetime > '2020-01-01' order by name"),
+            ["v1.0.0"]
+        );
+
+        // Lightweight tags have no tagger, and gitql can't compare a null.
+        git(repo_dir.path(), &["tag", "lightweight"]);
+        assert_eq!(
+            names(
+                "select name from tags where commit_datetime >= '2020-01-01 00:00:00' order by name"
+            ),
+            ["lightweight", "v1.0.0"]
+        );
+        assert!(names("select name from tags where commit_datetime < '2020-01-01'").is_empty());
+    }
+
+    #[test]
+    fn test_git_data_provider_branches_report_upstream_divergence() {
+        let repo_dir = init_temp_repo();
+        git(repo_dir.path(), &["branch", "-M", "main"]);
+        git(repo_dir.path(), &["branch", "--track", "feature", "main"]);
+        commit_file(repo_dir.path(), "CHANGELOG.md", "v2", "second commit", None);
+
+        let repo = gix::open(repo_dir.path()).expect("open repo");
+        let provider = GitDataProvider::new(vec![repo]);
+        let rows = provider
+            .provide(
+                "branches",
+                &[
+                    "name".to_string(),
+                    "upstream".to_string(),
+                    "ahead".to_string(),
+                    "behind".to_string(),
+                    "is_merged_into_head".to_string(),
+                ],
+            )
+            .expect("provide branches");
+
+        let feature = rows
+            .iter()
+            .find(|row| row.values[0].as_text().as_deref() == Some("refs/heads/feature"))
+            .expect("feature branch is listed");
+        assert_eq!(feature.values[1].as_text().as_deref(), Some("main"));
+        assert_eq!(feature.values[2].as_int(), Some(0));
+        assert_eq!(feature.values[3].as_int(), Some(1));
+        assert_eq!(feature.values[4].as_bool(), Some(true));
+
+        let main = rows
+            .iter()
+            .find(|row| row.values[0].as_text().as_deref() == Some("refs/heads/main"))
+            .expect("main branch is listed");
+        assert!(main.values[1].is_null());
+        assert!(main.values[2].is_null());
+    }
+
+    #[test]
+    fn test_git_data_provider_status_reports_worktree_and_index_changes() {
+        let repo_dir = init_temp_repo();
+        let repo_path = repo_dir.path();
+        fs::write(repo_path.join("README.md"), "changed").expect("write file");
+        fs::write(repo_path.join("staged.txt"), "staged").expect("write file");
+        fs::write(repo_path.join("untracked.txt"), "untracked").expect("write file");
+        fs::write(repo_path.join(".gitignore"), "*.log\n").expect("write file");
+        fs::write(repo_path.join("debug.log"), "ignored").expect("write file");
+        git(repo_path, &["add", "staged.txt"]);
+
+        let repo = gix::open(repo_path).expect("open repo");
+        let provider = GitDataProvider::new(vec![repo]);
+        let rows = provider
+            .provide(
+                "status",
+                &[
+                    "path".to_string(),
+                    "index_status".to_string(),
+                    "worktree_status".to_string(),
+                    "is_untracked".to_string(),
+                    "is_ignored".to_string(),
+                ],
+            )
+            .expect("provide status");
+
+        let path = |name: &str| {
+            rows.iter()
+                .find(|row| row.values[0].as_text().as_deref() == Some(name))
+                .unwrap_or_else(|| panic!("{name} is listed"))
+        };
+        assert_eq!(
+            path("README.md").values[2].as_text().as_deref(),
+            Some("modified")
+        );
+        assert_eq!(
+            path("staged.txt").values[1].as_text().as_deref(),
+            Some("added")
+        );
+        assert_eq!(path("untracked.txt").values[3].as_bool(), Some(true));
+        assert_eq!(path("debug.log").values[4].as_bool(), Some(true));
+    }
+
+    #[test]
+    fn test_json_and_yaml_output_keep_native_types() {
+        let repo_dir = init_temp_repo();
+        let repo_path = repo_dir.path().to_string_lossy().to_string();
+        let repo = gix::open(&repo_path).expect("open repo");
+        let query = "select commit_count, is_head, updated, upstream from branches";
+
+        let mut query_arguments = QueryArguments {
+            repos: vec![repo_path],
+            output_format: OutputFormat::Json,
+            pagination: false,
+            page_size: 10,
+            offset: 0,
+            analysis: false,
+            nest_by: None,
+        };
+        let mut env = gitql_environment();
+        let output = execute_gitql_query(
+            query.to_string(),
+            &query_arguments,
+            GitDataProvider::new(vec![repo.clone()]),
+            &mut env,
+            test_output_options(true),
+        )
+        .expect("execute query");
+        let QueryOutput::Value(value) = output else {
+            panic!("expected serialized output");
+        };
+        let json: serde_json::Value =
+            serde_json::from_str(value.as_str().expect("json text")).expect("parse json");
+        let branch = &json[0];
+        assert_eq!(branch["commit_count"], serde_json::json!(1));
+        assert_eq!(branch["is_head"], serde_json::json!(true));
+        assert!(branch["upstream"].is_null());
+        let updated = branch["updated"].as_str().expect("updated string");
+        assert!(chrono::DateTime::parse_from_rfc3339(updated).is_ok());
+
+        query_arguments.output_format = OutputFormat::Yaml;
+        let output = execute_gitql_query(
+            query.to_string(),
+            &query_arguments,
+            GitDataProvider::new(vec![repo.clone()]),
+            &mut env,
+            test_output_options(true),
+        )
+        .expect("execute query");
+        let QueryOutput::Value(value) = output else {
+            panic!("expected serialized output");
         };
+        let yaml: serde_yaml::Value =
+            serde_yaml::from_str(value.as_str().expect("yaml text")).expect("parse yaml");
+        assert_eq!(yaml[0]["commit_count"], serde_yaml::Value::from(1));
+        assert_eq!(yaml[0]["is_head"], serde_yaml::Value::Bool(true));
+        assert!(yaml[0]["upstream"].is_null());
+
+        let output = execute_gitql_query(
+            query.to_string(),
+            &query_arguments,
+            GitDataProvider::new(vec![repo]),
+            &mut env,
+            test_output_options(false),
+        )
+        .expect("execute query");
+        let QueryOutput::Value(value) = output else {
+            panic!("expected structured output");
+        };
+        let branches = value.as_list().expect("list of branches");
+        let branch = branches[0].as_record().expect("branch record");
+        assert_eq!(
+            branch.get("commit_count").and_then(|v| v.as_int().ok()),
+            Some(1)
+        );
+        assert_eq!(
+            branch.get("is_head").and_then(|v| v.as_bool().ok()),
+            Some(true)
+        );
+        assert!(branch.get("upstream").is_some_and(Value::is_nothing));
+    }
 
-        let schema = Schema {
-            tables_fields_names: tables_fields_names().to_owned(),
-            tables_fields_types: tables_fields_types().to_owned(),
+    #[test]
+    fn test_query_results_render_ndjson_tsv_markdown_and_html() {
+        let repo_dir = init_temp_repo();
+        commit_file(repo_dir.path(), "b.txt", "b\n", "pipe | and <tag>", None);
+        let repo_path = repo_dir.path().to_string_lossy().to_string();
+        let repo = gix::open(&repo_path).expect("open repo");
+        let mut env = gitql_environment();
+
+        let mut render = |output_format| {
+            let query_arguments = QueryArguments {
+                repos: vec![repo_path.clone()],
+                output_format,
+                pagination: false,
+                page_size: 10,
+                offset: 0,
+                analysis: false,
+                nest_by: None,
+            };
+            let output = execute_gitql_query(
+                "select title, parents_count from commits".to_string(),
+                &query_arguments,
+                GitDataProvider::new(vec![repo.clone()]),
+                &mut env,
+                test_output_options(true),
+            )
+            .expect("execute query");
+            let QueryOutput::Value(value) = output else {
+                panic!("expected text output");
+            };
+            value.coerce_into_string().expect("text output")
         };
 
-        let std_signatures = gitql_functions::gitql_std_signatures();
-        let std_functions = gitql_functions::gitql_std_functions();
-        let aggregation_signatures = aggregation_function_signatures();
-        let aggregation_functions = aggregation_functions();
+        let ndjson = render(OutputFormat::Ndjson);
+        let lines: Vec<serde_json::Value> = ndjson
+            .lines()
+            .map(|line| serde_json::from_str(line).expect("json line"))
+            .collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0]["parents_count"], serde_json::json!(1));
+
+        let tsv = render(OutputFormat::Tsv);
+        assert!(tsv.starts_with("title\tparents_count\n"));
+        assert!(tsv.contains("pipe | and <tag>\t1\n"));
+
+        let markdown = render(OutputFormat::Markdown);
+        assert!(markdown.starts_with("| title | parents_count |\n| --- | --- |\n"));
+        assert!(markdown.contains("| pipe \\| and <tag> | 1 |\n"));
+
+        let html = render(OutputFormat::Html);
+        assert!(html.contains("<td>pipe | and &lt;tag&gt;</td><td>1</td>"));
+    }
+
+    #[test]
+    fn test_nest_by_groups_rows_under_each_column_value() {
+        let repo_dir = init_temp_repo();
+        commit_file(repo_dir.path(), "b.txt", "b\n", "second commit", None);
+        commit_file(repo_dir.path(), "c.txt", "c\n", "third commit", None);
+        let repo_path = repo_dir.path().to_string_lossy().to_string();
+        let repo = gix::open(&repo_path).expect("open repo");
+        let mut env = gitql_environment();
+
+        let mut run = |output_format, nest_by: &str, raw| {
+            let query_arguments = QueryArguments {
+                repos: vec![repo_path.clone()],
+                output_format,
+                pagination: false,
+                page_size: 10,
+                offset: 0,
+                analysis: false,
+                nest_by: Some(nest_by.to_string()),
+            };
+            execute_gitql_query(
+                "select title, parents_count from commits".to_string(),
+                &query_arguments,
+                GitDataProvider::new(vec![repo.clone()]),
+                &mut env,
+                test_output_options(raw),
+            )
+        };
+
+        let Ok(QueryOutput::Value(value)) = run(OutputFormat::Table, "parents_count", false) else {
+            panic!("expected nested record");
+        };
+        let nested = value.as_record().expect("nested record");
+        assert_eq!(nested.columns().collect::<Vec<_>>(), ["1", "0"]);
+        let with_parent = nested.get("1").and_then(|rows| rows.as_list().ok());
+        assert_eq!(with_parent.map(<[Value]>::len), Some(2));
+
+        let Ok(QueryOutput::Value(value)) = run(OutputFormat::Json, "parents_count", true) else {
+            panic!("expected nested json");
+        };
+        let json: serde_json::Value =
+            serde_json::from_str(value.as_str().expect("json text")).expect("parse json");
+        assert_eq!(json["0"][0]["title"], serde_json::json!("initial commit"));
+
+        assert!(run(OutputFormat::Csv, "parents_count", false).is_err());
+        assert!(run(OutputFormat::Table, "missing", false).is_err());
+    }
+
+    #[test]
+    fn test_pagination_returns_requested_page_with_totals() {
+        let repo_dir = init_temp_repo();
+        commit_file(repo_dir.path(), "b.txt", "b\n", "second commit", None);
+        commit_file(repo_dir.path(), "c.txt", "c\n", "third commit", None);
+        let repo_path = repo_dir.path().to_string_lossy().to_string();
+        let repo = gix::open(&repo_path).expect("open repo");
+        let mut env = gitql_environment();
+
+        let mut page_at = |offset| {
+            let query_arguments = QueryArguments {
+                repos: vec![repo_path.clone()],
+                output_format: OutputFormat::Table,
+                pagination: true,
+                page_size: 2,
+                offset,
+                analysis: false,
+                nest_by: None,
+            };
+            let output = execute_gitql_query(
+                "select title from commits".to_string(),
+                &query_arguments,
+                GitDataProvider::new(vec![repo.clone()]),
+                &mut env,
+                test_output_options(false),
+            )
+            .expect("execute query");
+            let QueryOutput::Value(value) = output else {
+                panic!("expected page record");
+            };
+            value.into_record().expect("page record")
+        };
+        let int = |page: &nu_protocol::Record, column| {
+            page.get(column).and_then(|value| value.as_int().ok())
+        };
+        let titles = |page: &nu_protocol::Record| -> Vec<String> {
+            page.get("rows")
+                .and_then(|rows| rows.as_list().ok())
+                .expect("rows")
+                .iter()
+                .filter_map(|row| row.get_data_by_key("title"))
+                .filter_map(|title| title.as_str().ok().map(str::to_string))
+                .collect()
+        };
+
+        let first = page_at(0);
+        assert_eq!(int(&first, "page"), Some(1));
+        assert_eq!(int(&first, "pages"), Some(2));
+        assert_eq!(int(&first, "total"), Some(3));
+        assert_eq!(titles(&first), ["third commit", "second commit"]);
+
+        let second = page_at(2);
+        assert_eq!(int(&second, "page"), Some(2));
+        assert_eq!(titles(&second), ["initial commit"]);
+
+        assert!(titles(&page_at(10)).is_empty());
+    }
+
+    #[test]
+    fn test_git_data_provider_remotes_report_urls_and_refspecs() {
+        let repo_dir = init_temp_repo();
+        let repo_path = repo_dir.path();
+        git(
+            repo_path,
+            &[
+                "remote",
+                "add",
+                "origin",
+                "https://git.example.com/team/app.git",
+            ],
+        );
+        git(
+            repo_path,
+            &[
+                "remote",
+                "add",
+                "--no-tags",
+                "mirror",
+                "https://old.example.com/app.git",
+            ],
+        );
+        git(
+            repo_path,
+            &[
+                "remote",
+                "set-url",
+                "--push",
+                "mirror",
+                "ssh://git@old.example.com/app.git",
+            ],
+        );
+
+        let repo = gix::open(repo_path).expect("open repo");
+        let provider = GitDataProvider::new(vec![repo]);
+        let rows = provider
+            .provide(
+                "remotes",
+                &[
+                    "name".to_string(),
+                    "fetch_url".to_string(),
+                    "push_url".to_string(),
+                    "fetch_refspecs".to_string(),
+                    "tag_mode".to_string(),
+                    "is_default".to_string(),
+                ],
+            )
+            .expect("provide remotes");
+
+        let text = |row: &gitql_core::object::Row, index: usize| row.values[index].as_text();
+        assert_eq!(rows.len(), 2);
+        let (mirror, origin) = (&rows[0], &rows[1]);
+        assert_eq!(text(mirror, 0).as_deref(), Some("mirror"));
+        assert_eq!(
+            text(mirror, 2).as_deref(),
+            Some("ssh://git@old.example.com/app.git")
+        );
+        assert_eq!(text(mirror, 4).as_deref(), Some("none"));
+        assert_eq!(mirror.values[5].as_bool(), Some(false));
+
+        assert_eq!(
+            text(origin, 1).as_deref(),
+            Some("https://git.example.com/team/app.git")
+        );
+        assert_eq!(text(origin, 2), text(origin, 1));
+        assert_eq!(
+            text(origin, 3).as_deref(),
+            Some("+refs/heads/*:refs/remotes/origin/*")
+        );
+        assert_eq!(text(origin, 4).as_deref(), Some("auto"));
+        assert_eq!(origin.values[5].as_bool(), Some(true));
+    }
+
+    #[test]
+    fn test_stashes_list_latest_first_with_branch() {
+        let repo_dir = init_temp_repo();
+        let repo_path = repo_dir.path();
+        git(repo_path, &["checkout", "-q", "-b", "topic"]);
+        fs::write(repo_path.join("README.md"), "first change").expect("write file");
+        git(repo_path, &["stash", "-q"]);
+        git(repo_path, &["checkout", "-q", "-b", "feature"]);
+        fs::write(repo_path.join("README.md"), "second change").expect("write file");
+        fs::write(repo_path.join("notes.txt"), "notes").expect("write file");
+        git(repo_path, &["add", "notes.txt"]);
+        git(
+            repo_path,
+            &["stash", "push", "-q", "-m", "half-done feature"],
+        );
+
+        let repo_path = repo_path.to_string_lossy().to_string();
+        let query_arguments = QueryArguments {
+            repos: vec![repo_path.clone()],
+            output_format: OutputFormat::Json,
+            pagination: false,
+            page_size: 10,
+            offset: 0,
+            analysis: false,
+            nest_by: None,
+        };
+        let output = execute_gitql_query(
+            "select index, branch, message, files_changed from stashes".to_string(),
+            &query_arguments,
+            GitDataProvider::new(vec![gix::open(&repo_path).expect("open repo")]),
+            &mut gitql_environment(),
+            test_output_options(false),
+        )
+        .expect("execute query");
+        let QueryOutput::Value(value) = output else {
+            panic!("expected records");
+        };
+        let rows = value.as_list().expect("rows");
+        let field = |row: &Value, key| row.get_data_by_key(key).expect("stash column");
+
+        assert_eq!(rows.len(), 2);
+        assert_eq!(field(&rows[0], "index").as_int().ok(), Some(0));
+        assert_eq!(field(&rows[0], "branch").as_str().ok(), Some("feature"));
+        assert_eq!(
+            field(&rows[0], "message").as_str().ok(),
+            Some("On feature: half-done feature")
+        );
+        assert_eq!(field(&rows[0], "files_changed").as_int().ok(), Some(2));
+        assert_eq!(field(&rows[1], "index").as_int().ok(), Some(1));
+        assert_eq!(field(&rows[1], "branch").as_str().ok(), Some("topic"));
+        assert_eq!(field(&rows[1], "files_changed").as_int().ok(), Some(1));
+    }
+
+    #[test]
+    fn test_reflog_records_head_and_branch_updates() {
+        let repo_dir = init_temp_repo();
+        let repo_path = repo_dir.path();
+        commit_file(repo_path, "notes.txt", "notes", "add notes", None);
+        git(repo_path, &["reset", "-q", "--hard", "HEAD~1"]);
+        git(
+            repo_path,
+            &[
+                "update-ref",
+                "-m",
+                "deploy: promote build",
+                "refs/heads/deployed",
+                "HEAD",
+            ],
+        );
+
+        let repo = gix::open(repo_path).expect("open repo");
+        let initial_id = repo.head_id().expect("head id").to_string();
+        let branch = repo
+            .head_name()
+            .expect("head name")
+            .expect("branch checked out")
+            .as_bstr()
+            .to_string();
+        let columns = [
+            "ref_name".to_string(),
+            "index".to_string(),
+            "old_id".to_string(),
+            "new_id".to_string(),
+            "operation".to_string(),
+        ];
+        let text = |row: &gitql_core::object::Row, index: usize| row.values[index].as_text();
+        let rows_of = |rows: &[gitql_core::object::Row], ref_name: &str| -> usize {
+            rows.iter()
+                .filter(|row| text(row, 0).as_deref() == Some(ref_name))
+                .count()
+        };
+
+        let head_rows = GitDataProvider::new(vec![repo.clone()])
+            .provide("reflog", &columns)
+            .expect("provide reflog");
+        assert_eq!(head_rows.len(), 3);
+        assert_eq!(rows_of(&head_rows, "HEAD"), 3);
+
+        let reset = &head_rows[0];
+        assert_eq!(reset.values[1].as_int(), Some(0));
+        assert_eq!(text(reset, 3), Some(initial_id));
+        assert_eq!(text(reset, 4).as_deref(), Some("reset"));
+        assert_eq!(text(&head_rows[1], 4).as_deref(), Some("commit"));
+        assert_eq!(text(&head_rows[2], 4).as_deref(), Some("commit"));
+        assert!(head_rows[2].values[2].is_null());
+
+        let all_rows = GitDataProvider::new(vec![repo])
+            .with_all_refs(true)
+            .provide("reflog", &columns)
+            .expect("provide reflog");
+        assert_eq!(rows_of(&all_rows, "HEAD"), 3);
+        assert_eq!(rows_of(&all_rows, &branch), 3);
+        let deployed = all_rows
+            .iter()
+            .find(|row| text(row, 0).as_deref() == Some("refs/heads/deployed"))
+            .expect("deployed reflog");
+        assert!(deployed.values[4].is_null());
+    }
+
+    #[test]
+    fn test_worktrees_list_main_and_linked_checkouts() {
+        let repo_dir = init_temp_repo();
+        let repo_path = repo_dir.path();
+        let worktrees_dir = TempDir::new().expect("create temp dir");
+        let feature_path = worktrees_dir.path().join("feature");
+        let release_path = worktrees_dir.path().join("release");
+        let gone_path = worktrees_dir.path().join("gone");
+        let [feature, release, gone] = [&feature_path, &release_path, &gone_path]
+            .map(|path| path.to_string_lossy().to_string());
+        git(
+            repo_path,
+            &["worktree", "add", "-q", "-b", "feature", &feature],
+        );
+        git(repo_path, &["worktree", "add", "-q", "--detach", &release]);
+        git(
+            repo_path,
+            &["worktree", "lock", "--reason", "on a usb drive", &release],
+        );
+        git(repo_path, &["worktree", "add", "-q", "--detach", &gone]);
+        fs::remove_dir_all(&gone_path).expect("remove worktree");
+
+        // Listing from a linked worktree still reports every checkout.
+        let repo = gix::open(&feature_path).expect("open worktree");
+        let head_commit = repo.head_id().expect("head id").to_string();
+        let provider = GitDataProvider::new(vec![repo]);
+        let rows = provider
+            .provide(
+                "worktrees",
+                &[
+                    "id".to_string(),
+                    "path".to_string(),
+                    "head_commit".to_string(),
+                    "branch".to_string(),
+                    "is_main".to_string(),
+                    "is_locked".to_string(),
+                    "lock_reason".to_string(),
+                    "is_prunable".to_string(),
+                ],
+            )
+            .expect("provide worktrees");
+
+        let text = |row: &gitql_core::object::Row, index: usize| row.values[index].as_text();
+        let flag = |row: &gitql_core::object::Row, index: usize| row.values[index].as_bool();
+        assert_eq!(rows.len(), 4);
+
+        let main = &rows[0];
+        assert!(main.values[0].is_null());
+        assert_eq!(
+            text(main, 1).map(std::path::PathBuf::from),
+            Some(repo_path.to_path_buf())
+        );
+        assert_eq!(text(main, 2), Some(head_commit.clone()));
+        assert_eq!(flag(main, 4), Some(true));
+
+        let feature = &rows[1];
+        assert_eq!(text(feature, 0).as_deref(), Some("feature"));
+        assert_eq!(
+            text(feature, 1).map(std::path::PathBuf::from),
+            Some(feature_path)
+        );
+        assert_eq!(text(feature, 3).as_deref(), Some("feature"));
+        assert_eq!(flag(feature, 4), Some(false));
+        assert_eq!(flag(feature, 7), Some(false));
+
+        let gone = &rows[2];
+        assert_eq!(text(gone, 0).as_deref(), Some("gone"));
+        assert!(gone.values[3].is_null());
+        assert_eq!(flag(gone, 7), Some(true));
+
+        let release = &rows[3];
+        assert_eq!(text(release, 0).as_deref(), Some("release"));
+        assert_eq!(text(release, 2), Some(head_commit));
+        assert_eq!(flag(release, 5), Some(true));
+        assert_eq!(text(release, 6).as_deref(), Some("on a usb drive"));
+        assert_eq!(flag(release, 7), Some(false));
+    }
+
+    #[test]
+    fn test_select_count_with_analysis_returns_value() {
+        let repo_dir = init_temp_repo();
+        let repo_path = repo_dir.path().to_string_lossy().to_string();
+        let repo = gix::open(&repo_path).expect("open repo");
 
-        let mut env = Environment::new(schema);
-        env.with_standard_functions(&std_signatures, std_functions);
-        env.with_aggregation_functions(&aggregation_signatures, aggregation_functions);
+        let query_arguments = QueryArguments {
+            repos: vec![repo_path],
+            output_format: OutputFormat::Table,
+            pagination: false,
+            page_size: 10,
+            offset: 0,
+            analysis: true,
+            nest_by: None,
+        };
 
-        let value = execute_gitql_query(
+        let mut env = gitql_environment();
+
+        let output = execute_gitql_query(
             "select count(*) from commits".to_string(),
             &query_arguments,
-            &[repo],
+            GitDataProvider::new(vec![repo]),
             &mut env,
+            test_output_options(false),
         )
         .expect("execute query");
 
-        assert!(value.as_str().is_ok() || value.as_list().is_ok());
+        let QueryOutput::Value(value) = output else {
+            panic!("expected analysis record");
+        };
+        let record = value.as_record().expect("analysis record");
+        let result = record
+            .get("result")
+            .and_then(|result| result.as_list().ok());
+        assert_eq!(result.map(<[Value]>::len), Some(1));
+
+        let analysis = record
+            .get("analysis")
+            .and_then(|analysis| analysis.as_record().ok())
+            .expect("analysis");
+        for timing in ["tokenize", "parse", "evaluate", "render"] {
+            assert!(
+                analysis
+                    .get(timing)
+                    .is_some_and(|value| value.as_duration().is_ok())
+            );
+        }
+        let int = |column| analysis.get(column).and_then(|value| value.as_int().ok());
+        assert_eq!(int("rows_scanned"), Some(1));
+        assert_eq!(int("rows_returned"), Some(1));
+
+        let provide = analysis
+            .get("provide_per_table")
+            .and_then(|provide| provide.as_list().ok())
+            .expect("provide timings");
+        assert_eq!(provide.len(), 1);
+        let table = provide[0]
+            .get_data_by_key("table")
+            .and_then(|table| table.as_str().ok().map(str::to_string));
+        assert_eq!(table.as_deref(), Some("commits"));
+    }
+
+    #[test]
+    fn test_commit_walk_applies_pushed_down_bounds() {
+        let repo_dir = init_temp_repo();
+        let repo_path = repo_dir.path();
+        commit_file(
+            repo_path,
+            "a.txt",
+            "january",
+            "january",
+            Some("2024-01-15T12:00:00Z"),
+        );
+        commit_file(
+            repo_path,
+            "b.txt",
+            "june",
+            "june",
+            Some("2024-06-15T12:00:00Z"),
+        );
+        commit_file(
+            repo_path,
+            "c.txt",
+            "july",
+            "july",
+            Some("2024-07-15T12:00:00Z"),
+        );
+        let repo_path = repo_path.to_string_lossy().to_string();
+        let repo = gix::open(&repo_path).expect("open repo");
+
+        let column = |query: &str, column: &str| -> Vec<String> {
+            let query_arguments = QueryArguments {
+                repos: vec![repo_path.clone()],
+                output_format: OutputFormat::Json,
+                pagination: false,
+                page_size: 10,
+                offset: 0,
+                analysis: false,
+                nest_by: None,
+            };
+            let output = execute_gitql_query(
+                query.to_string(),
+                &query_arguments,
+                GitDataProvider::new(vec![repo.clone()]),
+                &mut gitql_environment(),
+                test_output_options(false),
+            )
+            .expect("execute query");
+            let QueryOutput::Value(value) = output else {
+                panic!("expected records");
+            };
+            value
+                .as_list()
+                .expect("rows")
+                .iter()
+                .filter_map(|row| row.get_data_by_key(column))
+                .filter_map(|value| value.as_str().ok().map(str::to_string))
+                .collect()
+        };
+        let titles = |query: &str| column(query, "title");
+
+        // The initial commit is dated now, so it passes the bound even though
+        // the commits after it are older.
+        assert_eq!(
+            titles("select title from commits where datetime >= '2024-06-01 00:00:00'"),
+            ["july", "june", "initial commit"]
+        );
+        assert_eq!(
+            titles(
+                "select title from commits where datetime > '2024-01-01 00:00:00' \
+                 and datetime < '2024-07-01 00:00:00'"
+            ),
+            ["june", "january"]
+        );
+        assert_eq!(
+            titles("select title from commits where author_email = 'test@example.com' limit 2"),
+            ["july", "june"]
+        );
+
+        // The walk itself stops at the limit rather than leaving it to the engine.
+        let commit_filter = CommitFilter {
+            table: "commits".to_string(),
+            since: Some(1_704_067_200),
+            limit: Some(1),
+            ..CommitFilter::default()
+        };
+        let rows = GitDataProvider::new(vec![repo.clone()])
+            .with_commit_filter(Some(commit_filter))
+            .provide("commits", &["title".to_string()])
+            .expect("provide commits");
+        assert_eq!(rows.len(), 1);
+
+        let june = repo
+            .rev_parse_single("HEAD~1")
+            .expect("june commit")
+            .to_string();
+        let query = format!("select commit_id from diffs where commit_id = '{june}'");
+        assert_eq!(column(&query, "commit_id"), [june]);
+    }
+
+    #[test]
+    fn test_pushed_down_since_keeps_commits_behind_an_older_one() {
+        let repo_dir = init_temp_repo();
+        let repo_path = repo_dir.path();
+        commit_file(
+            repo_path,
+            "a.txt",
+            "june",
+            "june",
+            Some("2024-06-15T12:00:00Z"),
+        );
+        // An imported or rebased commit whose date is older than its parent's.
+        commit_file(
+            repo_path,
+            "b.txt",
+            "imported",
+            "imported",
+            Some("2019-03-01T12:00:00Z"),
+        );
+        commit_file(
+            repo_path,
+            "c.txt",
+            "july",
+            "july",
+            Some("2024-07-15T12:00:00Z"),
+        );
+        let repo = gix::open(repo_path).expect("open repo");
+
+        let query = "select title, datetime from commits where datetime >= '2024-06-01'";
+        let tokens = tokenize_gitql_query(query, Span::test_data()).expect("tokenize");
+        let queries =
+            parse_gitql_tokens(query, tokens, &mut gitql_environment(), Span::test_data())
+                .expect("parse");
+        let commit_filter = CommitFilter::from_queries(&queries).expect("pushdown");
+        let cutoff = commit_filter.since.expect("since bound");
+
+        let columns = ["title".to_string(), "datetime".to_string()];
+        let titles = |rows: Vec<gitql_core::object::Row>| -> Vec<String> {
+            rows.iter()
+                .filter(|row| {
+                    row.values[1]
+                        .as_date_time()
+                        .is_some_and(|time| time >= cutoff)
+                })
+                .filter_map(|row| row.values[0].as_text())
+                .collect()
+        };
+        let pushed_down = GitDataProvider::new(vec![repo.clone()])
+            .with_commit_filter(Some(commit_filter))
+            .provide("commits", &columns)
+            .expect("provide with pushdown");
+        let unfiltered = GitDataProvider::new(vec![repo])
+            .provide("commits", &columns)
+            .expect("provide without pushdown");
+
+        assert_eq!(titles(pushed_down), ["july", "june", "initial commit"]);
+        assert_eq!(titles(unfiltered), ["july", "june", "initial commit"]);
+    }
+
+    #[test]
+    fn test_parallel_provide_returns_the_same_cells_for_every_table() {
+        let first_dir = init_temp_repo();
+        let first_path = first_dir.path();
+        commit_file(first_path, "b.txt", "b\n", "second commit", None);
+        git(first_path, &["tag", "-a", "v1.0", "-m", "first release"]);
+        git(first_path, &["branch", "feature"]);
+        git(
+            first_path,
+            &["remote", "add", "origin", "https://example.com/repo.git"],
+        );
+        fs::write(first_path.join("README.md"), "stashed").expect("write file");
+        git(first_path, &["stash", "-q"]);
+        fs::write(first_path.join("untracked.txt"), "new").expect("write file");
+        let second_dir = init_temp_repo();
+        let repos = vec![
+            gix::open(first_path).expect("open first repo"),
+            gix::open(second_dir.path()).expect("open second repo"),
+        ];
+
+        let mut tables: Vec<_> = gitql_schema::tables_fields_names().iter().collect();
+        tables.sort();
+        for (table, columns) in tables {
+            let columns: Vec<String> = columns.iter().map(|column| column.to_string()).collect();
+            let cells = |threads| -> Vec<Vec<(String, String)>> {
+                GitDataProvider::new(repos.clone())
+                    .with_path(Some("README.md".to_string()))
+                    .with_threads(threads)
+                    .provide(table, &columns)
+                    .unwrap_or_else(|err| panic!("provide {table}: {err}"))
+                    .iter()
+                    .map(|row| {
+                        row.values
+                            .iter()
+                            .map(|value| (value.data_type().literal(), value.literal()))
+                            .collect()
+                    })
+                    .collect()
+            };
+
+            let sequential = cells(1);
+            assert!(!sequential.is_empty(), "{table} has rows");
+            assert_eq!(cells(2), sequential, "{table} cells differ in parallel");
+        }
+    }
+
+    #[test]
+    fn test_parallel_provide_keeps_repository_order() {
+        let first_dir = init_temp_repo();
+        commit_file(first_dir.path(), "b.txt", "b\n", "second commit", None);
+        let second_dir = init_temp_repo();
+        let repos = vec![
+            gix::open(first_dir.path()).expect("open first repo"),
+            gix::open(second_dir.path()).expect("open second repo"),
+        ];
+        let columns = ["title".to_string(), "repo".to_string()];
+
+        let cells = |threads| -> Vec<(String, String)> {
+            let provider = GitDataProvider::new(repos.clone()).with_threads(threads);
+            provider
+                .provide("commits", &columns)
+                .expect("provide commits")
+                .iter()
+                .map(|row| {
+                    let text =
+                        |index: usize| row.values[index].as_text().expect("text value").to_string();
+                    (text(0), text(1))
+                })
+                .collect()
+        };
+
+        let parallel = cells(2);
+        assert_eq!(parallel, cells(1));
+        let titles: Vec<&str> = parallel.iter().map(|(title, _)| title.as_str()).collect();
+        assert_eq!(
+            titles,
+            ["second commit", "initial commit", "initial commit"]
+        );
+        assert_ne!(parallel[0].1, parallel[2].1);
+    }
+
+    #[test]
+    fn test_discover_finds_checkouts_bare_repos_and_worktrees() {
+        let root = TempDir::new().expect("create temp dir");
+
+        let service = init_temp_repo();
+        let checkout = root.path().join("service");
+        git(
+            root.path(),
+            &["clone", "-q", &service.path().to_string_lossy(), "service"],
+        );
+        git(root.path(), &["init", "-q", "--bare", "archive/old.git"]);
+        git(&checkout, &["worktree", "add", "-q", "../service-wt"]);
+        git(
+            &checkout,
+            &[
+                "-c",
+                "protocol.file.allow=always",
+                "submodule",
+                "add",
+                "-q",
+                &service.path().to_string_lossy(),
+                "vendor/lib",
+            ],
+        );
+        // An independent clone inside a checkout, as in a meta-repo.
+        git(&checkout, &["init", "-q", "tools/helper"]);
+        fs::create_dir_all(root.path().join("deep/er")).expect("create dirs");
+        git(&root.path().join("deep/er"), &["init", "-q", "nested"]);
+
+        let found = |max_depth, include_submodules| -> Vec<String> {
+            repo_discovery::discover_repositories(root.path(), max_depth, include_submodules)
+                .expect("discover")
+                .iter()
+                .map(|path| {
+                    path.strip_prefix(root.path())
+                        .expect("under root")
+                        .to_string_lossy()
+                        .into_owned()
+                })
+                .collect()
+        };
+
+        assert_eq!(
+            found(None, false),
+            [
+                "archive/old.git",
+                "deep/er/nested",
+                "service",
+                "service/tools/helper",
+                "service-wt",
+            ]
+        );
+        assert_eq!(
+            found(Some(2), false),
+            ["archive/old.git", "service", "service-wt"]
+        );
+        assert_eq!(
+            found(None, true),
+            [
+                "archive/old.git",
+                "deep/er/nested",
+                "service",
+                "service/tools/helper",
+                "service/vendor/lib",
+                "service-wt",
+            ]
+        );
+        for path in found(None, true) {
+            gix::open(root.path().join(&path)).expect("discovered path opens");
+        }
+    }
+
+    #[test]
+    fn test_keep_going_skips_failing_repositories() {
+        let repo_dir = init_temp_repo();
+        let empty_dir = TempDir::new().expect("create temp dir");
+        git(empty_dir.path(), &["init", "-q"]);
+        let missing = repo_dir
+            .path()
+            .join("missing")
+            .to_string_lossy()
+            .to_string();
+
+        let paths = vec![
+            repo_dir.path().to_string_lossy().to_string(),
+            missing.clone(),
+            empty_dir.path().to_string_lossy().to_string(),
+        ];
+        let (repos, open_errors) = open_git_repositories(&paths);
+        assert_eq!(repos.len(), 2);
+        assert!(validate_git_repositories(&paths).is_err());
+
+        let query_arguments = QueryArguments {
+            repos: paths,
+            output_format: OutputFormat::Table,
+            pagination: false,
+            page_size: 10,
+            offset: 0,
+            analysis: false,
+            nest_by: None,
+        };
+        let output = execute_gitql_query(
+            "select title from commits".to_string(),
+            &query_arguments,
+            GitDataProvider::new(repos).with_keep_going(true, open_errors),
+            &mut gitql_environment(),
+            test_output_options(false),
+        )
+        .expect("execute query");
+        let QueryOutput::Value(value) = output else {
+            panic!("expected keep-going record");
+        };
+        let record = value.into_record().expect("keep-going record");
+
+        let rows = record
+            .get("result")
+            .and_then(|result| result.as_list().ok())
+            .expect("result rows");
+        assert_eq!(rows.len(), 1);
+
+        let errors = record
+            .get("errors")
+            .and_then(|errors| errors.as_list().ok())
+            .expect("errors");
+        let field = |error: &Value, key| {
+            error
+                .get_data_by_key(key)
+                .and_then(|value| value.as_str().ok().map(str::to_string))
+        };
+        assert_eq!(errors.len(), 2);
+        assert_eq!(field(&errors[0], "repo"), Some(missing));
+        assert_eq!(field(&errors[0], "table"), None);
+        assert_eq!(field(&errors[1], "table").as_deref(), Some("commits"));
+        assert!(field(&errors[1], "error").is_some());
     }
 }
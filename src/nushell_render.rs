@@ -1,145 +1,374 @@
 use chrono::{TimeZone, Utc};
 use gitql_core::object::{GitQLObject, Row};
-use nu_protocol::{Record, Value as NuValue};
+use gitql_core::values::Value;
+use nu_protocol::{Record, Span, Value as NuValue};
+use std::collections::HashMap;
 
-pub fn render_objects(groups: &mut GitQLObject) -> NuValue {
+/// Lazily render every row of `groups` as a Nushell record, so callers can
+/// stream rows out without building the whole table first.
+pub fn render_rows(groups: &mut GitQLObject, span: Span) -> impl Iterator<Item = NuValue> + '_ {
+    let (titles, rows) = flat_rows(groups);
+    rows.iter().map(move |row| render_row(titles, row, span))
+}
+
+/// Titles and every result row. Groups are merged first, so each output
+/// format sees the same rows as the table.
+fn flat_rows(groups: &mut GitQLObject) -> (&[String], &[Row]) {
     if groups.len() > 1 {
         groups.flat();
     }
 
-    if groups.is_empty() || groups.groups[0].is_empty() {
-        return NuValue::test_string("No data to display".to_string());
+    let rows = groups.groups.first().map_or(&[][..], |group| &group.rows);
+    (&groups.titles, rows)
+}
+
+/// Result rows grouped by the value of `column`, keeping the order in which
+/// each value first appears.
+fn nest_rows<'a>(
+    titles: &[String],
+    rows: &'a [Row],
+    column: &str,
+) -> Result<Vec<(String, Vec<&'a Row>)>, String> {
+    let index = titles
+        .iter()
+        .position(|title| title == column)
+        .ok_or_else(|| format!("Column `{column}` is not in the query results"))?;
+
+    let mut nested: Vec<(String, Vec<&Row>)> = Vec::new();
+    let mut positions: HashMap<String, usize> = HashMap::new();
+    for row in rows {
+        let key = row.values[index].literal();
+        match positions.get(&key) {
+            Some(&position) => nested[position].1.push(row),
+            None => {
+                positions.insert(key.clone(), nested.len());
+                nested.push((key, vec![row]));
+            }
+        }
     }
+    Ok(nested)
+}
 
-    let gql_group = &groups.groups[0];
-    print_group_as_table(&groups.titles, &gql_group.rows)
+/// A record keyed by each value of `column`, holding the table rows that
+/// share it.
+pub fn render_nested_rows(
+    groups: &mut GitQLObject,
+    column: &str,
+    span: Span,
+) -> Result<NuValue, String> {
+    let (titles, rows) = flat_rows(groups);
+    let record = nest_rows(titles, rows, column)?
+        .into_iter()
+        .map(|(key, rows)| {
+            let rows = rows
+                .into_iter()
+                .map(|row| render_row(titles, row, span))
+                .collect();
+            (key, NuValue::list(rows, span))
+        })
+        .collect();
+    Ok(NuValue::record(record, span))
 }
 
-fn print_group_as_table(titles: &[String], rows: &[Row]) -> NuValue {
-    let mut table_row_val: Vec<NuValue> = Vec::with_capacity(rows.len());
+/// Same as [`render_nested_rows`], with the typed values of `--output json`.
+pub fn render_nested_typed_records(
+    groups: &mut GitQLObject,
+    column: &str,
+    span: Span,
+) -> Result<NuValue, String> {
+    let (titles, rows) = flat_rows(groups);
+    let typed_cell = |value: &(dyn Value + 'static)| typed_cell(value, span);
+    let record = nest_rows(titles, rows, column)?
+        .into_iter()
+        .map(|(key, rows)| {
+            let rows = rows
+                .into_iter()
+                .map(|row| row_to_record(titles, row, span, typed_cell))
+                .collect();
+            (key, NuValue::list(rows, span))
+        })
+        .collect();
+    Ok(NuValue::record(record, span))
+}
 
-    for row in rows {
-        let mut rec = Record::new();
-        for (column_name, column_value) in titles.iter().zip(&row.values) {
-            // eprintln!("{column_name:#?} - {:#?}", column_value.as_text());
-            // let dt = column_value.data_type();
-            // if let Some(int_val) = column_value.as_any().downcast_ref::<IntValue>() {
-            //     rec.insert(column_name, NuValue::Int(int_val.value));
-            // }
-
-            // use super::array::ArrayValue;
-            // use super::boolean::BoolValue;
-            // use super::date::DateValue;
-            // use super::datetime::DateTimeValue;
-            // use super::float::FloatValue;
-            // use super::integer::IntValue;
-            // use super::null::NullValue;
-            // use super::range::RangeValue;
-            // use super::text::TextValue;
-            // use super::time::TimeValue;
-            // }
-            match column_value {
-                v if v.is_array() => {
-                    if let Some(array_val) = v.as_array() {
-                        let array_str = array_val
-                            // .values
-                            .iter()
-                            .map(|v| v.literal())
-                            .collect::<Vec<_>>()
-                            .join(",");
-                        rec.insert(column_name, NuValue::test_string(array_str));
-                    }
-                }
-                v if v.is_bool() => {
-                    if let Some(boolean) = v.as_bool() {
-                        rec.insert(column_name, NuValue::test_bool(boolean));
-                    }
+pub fn render_nested_json(groups: &mut GitQLObject, column: &str) -> Result<String, String> {
+    let (titles, rows) = flat_rows(groups);
+    let object: serde_json::Map<String, serde_json::Value> = nest_rows(titles, rows, column)?
+        .into_iter()
+        .map(|(key, rows)| {
+            let rows = rows
+                .into_iter()
+                .map(|row| serde_json::Value::Object(row_to_json(titles, row)))
+                .collect();
+            (key, serde_json::Value::Array(rows))
+        })
+        .collect();
+    serde_json::to_string(&object).map_err(|err| err.to_string())
+}
+
+pub fn render_nested_yaml(groups: &mut GitQLObject, column: &str) -> Result<String, String> {
+    let (titles, rows) = flat_rows(groups);
+    let mapping: serde_yaml::Mapping = nest_rows(titles, rows, column)?
+        .into_iter()
+        .map(|(key, rows)| {
+            let rows = rows
+                .into_iter()
+                .map(|row| row_to_yaml(titles, row))
+                .collect();
+            (
+                serde_yaml::Value::String(key),
+                serde_yaml::Value::Sequence(rows),
+            )
+        })
+        .collect();
+    serde_yaml::to_string(&mapping).map_err(|err| err.to_string())
+}
+
+fn render_row(titles: &[String], row: &Row, span: Span) -> NuValue {
+    let mut rec = Record::new();
+    for (column_name, column_value) in titles.iter().zip(&row.values) {
+        // eprintln!("{column_name:#?} - {:#?}", column_value.as_text());
+        // let dt = column_value.data_type();
+        // if let Some(int_val) = column_value.as_any().downcast_ref::<IntValue>() {
+        //     rec.insert(column_name, NuValue::Int(int_val.value));
+        // }
+
+        // use super::array::ArrayValue;
+        // use super::boolean::BoolValue;
+        // use super::date::DateValue;
+        // use super::datetime::DateTimeValue;
+        // use super::float::FloatValue;
+        // use super::integer::IntValue;
+        // use super::null::NullValue;
+        // use super::range::RangeValue;
+        // use super::text::TextValue;
+        // use super::time::TimeValue;
+        // }
+        match column_value {
+            v if v.is_array() => {
+                if let Some(array_val) = v.as_array() {
+                    let array_str = array_val
+                        // .values
+                        .iter()
+                        .map(|v| v.literal())
+                        .collect::<Vec<_>>()
+                        .join(",");
+                    rec.insert(column_name, NuValue::string(array_str, span));
                 }
-                v if v.is_date() => {
-                    if let Some(date) = v.as_date() {
-                        rec.insert(column_name, NuValue::test_string(date.to_string()));
-                    }
+            }
+            v if v.is_bool() => {
+                if let Some(boolean) = v.as_bool() {
+                    rec.insert(column_name, NuValue::bool(boolean, span));
                 }
-                v if v.is_date_time() => {
-                    if let Some(date_time) = v.as_date_time() {
-                        let timestamp = date_time * 1_000_000_000;
-                        let dt = Utc.timestamp_nanos(timestamp);
-                        rec.insert(column_name, NuValue::test_date(dt.into()));
-                    }
+            }
+            v if v.is_date() => {
+                if let Some(date) = v.as_date() {
+                    rec.insert(column_name, NuValue::string(date.to_string(), span));
                 }
-                v if v.is_float() => {
-                    if let Some(float) = v.as_float() {
-                        rec.insert(column_name, NuValue::test_float(float));
-                    }
+            }
+            v if v.is_date_time() => {
+                if let Some(date_time) = v.as_date_time() {
+                    rec.insert(column_name, date_value(date_time, span));
                 }
-                v if v.as_range().is_some() => {
-                    if let Some(range) = v.as_range() {
-                        rec.insert(
-                            column_name,
-                            NuValue::test_string(format!("{}..{}", range.0, range.1)),
-                        );
-                    }
+            }
+            v if v.is_float() => {
+                if let Some(float) = v.as_float() {
+                    rec.insert(column_name, NuValue::float(float, span));
                 }
-                v if v.is_null() => {
-                    rec.insert(column_name, NuValue::test_nothing());
+            }
+            v if v.as_range().is_some() => {
+                if let Some(range) = v.as_range() {
+                    rec.insert(
+                        column_name,
+                        NuValue::string(format!("{}..{}", range.0, range.1), span),
+                    );
                 }
-                v if v.is_int() => {
-                    if let Some(int_value) = v.as_int() {
-                        rec.insert(column_name, NuValue::test_int(int_value));
-                    }
+            }
+            v if v.is_null() => {
+                rec.insert(column_name, NuValue::nothing(span));
+            }
+            v if v.is_int() => {
+                if let Some(int_value) = v.as_int() {
+                    rec.insert(column_name, NuValue::int(int_value, span));
                 }
-                v if v.is_text() => {
-                    if let Some(text) = v.as_text() {
-                        rec.insert(column_name, NuValue::test_string(text));
-                    }
+            }
+            v if v.is_text() => {
+                if let Some(text) = v.as_text() {
+                    rec.insert(column_name, NuValue::string(text, span));
                 }
-                v if v.is_time() => {
-                    if let Some(time) = v.as_time() {
-                        rec.insert(column_name, NuValue::test_string(time));
-                    }
+            }
+            v if v.is_time() => {
+                if let Some(time) = v.as_time() {
+                    rec.insert(column_name, NuValue::string(time, span));
                 }
-
-                _ => {}
             }
+
+            _ => {}
         }
-        table_row_val.push(NuValue::test_record(rec));
     }
-
-    NuValue::test_list(table_row_val)
+    NuValue::record(rec, span)
 }
 
 pub fn render_groups_to_json(groups: &mut GitQLObject) -> Option<String> {
-    let mut elements: Vec<serde_json::Value> = vec![];
-
-    if let Some(group) = groups.groups.first() {
-        let titles = &groups.titles;
-        for row in &group.rows {
-            let mut object = serde_json::Map::new();
-            for (i, value) in row.values.iter().enumerate() {
-                object.insert(
-                    titles[i].clone(),
-                    serde_json::Value::String(value.literal()),
-                );
-            }
-            elements.push(serde_json::Value::Object(object));
-        }
-    }
+    let (titles, rows) = flat_rows(groups);
+    let elements: Vec<serde_json::Value> = rows
+        .iter()
+        .map(|row| serde_json::Value::Object(row_to_json(titles, row)))
+        .collect();
 
     serde_json::to_string(&serde_json::Value::Array(elements)).ok()
 }
 
+fn row_to_json(titles: &[String], row: &Row) -> serde_json::Map<String, serde_json::Value> {
+    titles
+        .iter()
+        .zip(&row.values)
+        .map(|(title, value)| (title.clone(), value_to_json(value.as_ref())))
+        .collect()
+}
+
+/// Rows as records holding the values `--output json` describes, built from
+/// the GitQL values so dates stay Nushell `date` values.
+pub fn render_groups_to_typed_records(groups: &mut GitQLObject, span: Span) -> NuValue {
+    render_groups_to_records(groups, span, |value| typed_cell(value, span))
+}
+
+/// Rows as records of the same text cells as `--output csv`.
+pub fn render_groups_to_text_records(groups: &mut GitQLObject, span: Span) -> NuValue {
+    render_groups_to_records(groups, span, |value| {
+        NuValue::string(cell_text(value), span)
+    })
+}
+
+fn render_groups_to_records(
+    groups: &mut GitQLObject,
+    span: Span,
+    cell: impl Fn(&(dyn Value + 'static)) -> NuValue + Copy,
+) -> NuValue {
+    let (titles, rows) = flat_rows(groups);
+    let rows = rows
+        .iter()
+        .map(|row| row_to_record(titles, row, span, cell))
+        .collect();
+    NuValue::list(rows, span)
+}
+
+fn row_to_record(
+    titles: &[String],
+    row: &Row,
+    span: Span,
+    cell: impl Fn(&(dyn Value + 'static)) -> NuValue,
+) -> NuValue {
+    let record = titles
+        .iter()
+        .zip(&row.values)
+        .map(|(title, value)| (title.clone(), cell(value.as_ref())))
+        .collect::<Record>();
+    NuValue::record(record, span)
+}
+
+/// The Nushell type closest to a GitQL value. Unlike the table, arrays stay
+/// lists rather than being joined into text.
+fn typed_cell(value: &(dyn Value + 'static), span: Span) -> NuValue {
+    if value.is_null() {
+        NuValue::nothing(span)
+    } else if let Some(boolean) = value.as_bool() {
+        NuValue::bool(boolean, span)
+    } else if let Some(int) = value.as_int() {
+        NuValue::int(int, span)
+    } else if let Some(float) = value.as_float() {
+        NuValue::float(float, span)
+    } else if let Some(date_time) = value.as_date_time() {
+        date_value(date_time, span)
+    } else if let Some(date) = value.as_date() {
+        date_value(date, span)
+    } else if let Some(array) = value.as_array() {
+        NuValue::list(
+            array
+                .iter()
+                .map(|item| typed_cell(item.as_ref(), span))
+                .collect(),
+            span,
+        )
+    } else {
+        NuValue::string(value.literal(), span)
+    }
+}
+
+/// A Nushell `date` for a GitQL timestamp in seconds since the epoch.
+fn date_value(seconds: i64, span: Span) -> NuValue {
+    NuValue::date(Utc.timestamp_nanos(seconds * 1_000_000_000).into(), span)
+}
+
+/// One JSON object per line, for log pipelines and `lines | each { from json }`.
+pub fn render_groups_to_ndjson(groups: &mut GitQLObject) -> Option<String> {
+    let (titles, rows) = flat_rows(groups);
+    let mut lines = String::new();
+    for row in rows {
+        lines.push_str(&serde_json::to_string(&row_to_json(titles, row)).ok()?);
+        lines.push('\n');
+    }
+
+    Some(lines)
+}
+
 pub fn render_groups_to_csv(groups: &mut GitQLObject) -> Option<String> {
-    let mut writer = csv::Writer::from_writer(vec![]);
-    let _ = writer.write_record(groups.titles.clone());
-    let row_len = groups.titles.len();
-    if let Some(group) = groups.groups.first() {
-        for row in &group.rows {
-            let mut values_row: Vec<String> = Vec::with_capacity(row_len);
-            for value in &row.values {
-                values_row.push(value.literal());
-            }
-            let _ = writer.write_record(values_row);
-        }
+    let (titles, cells) = group_cells(groups);
+    render_cells_to_delimited(titles, &cells, b',')
+}
+
+pub fn render_groups_to_tsv(groups: &mut GitQLObject) -> Option<String> {
+    let (titles, cells) = group_cells(groups);
+    render_cells_to_delimited(titles, &cells, b'\t')
+}
+
+pub fn render_groups_to_markdown(groups: &mut GitQLObject) -> String {
+    let (titles, cells) = group_cells(groups);
+    render_cells_to_markdown(titles, &cells)
+}
+
+pub fn render_groups_to_html(groups: &mut GitQLObject) -> String {
+    let (titles, cells) = group_cells(groups);
+    render_cells_to_html(titles, &cells)
+}
+
+/// Titles and the literal text of every result cell.
+fn group_cells(groups: &mut GitQLObject) -> (&[String], Vec<Vec<String>>) {
+    let (titles, rows) = flat_rows(groups);
+    let cells = rows
+        .iter()
+        .map(|row| {
+            row.values
+                .iter()
+                .map(|value| cell_text(value.as_ref()))
+                .collect()
+        })
+        .collect();
+    (titles, cells)
+}
+
+/// Text of a cell in the text formats, where null is an empty cell.
+fn cell_text(value: &(dyn Value + 'static)) -> String {
+    if value.is_null() {
+        String::new()
+    } else {
+        value.literal()
+    }
+}
+
+/// CSV, or TSV with a tab `delimiter`. Cells holding the delimiter, quotes or
+/// newlines are quoted, which `from csv`/`from tsv` read back unchanged.
+pub fn render_cells_to_delimited(
+    titles: &[String],
+    rows: &[Vec<String>],
+    delimiter: u8,
+) -> Option<String> {
+    let mut writer = csv::WriterBuilder::new()
+        .delimiter(delimiter)
+        .from_writer(vec![]);
+    let _ = writer.write_record(titles);
+    for row in rows {
+        let _ = writer.write_record(row);
     }
 
     writer
@@ -148,22 +377,95 @@ pub fn render_groups_to_csv(groups: &mut GitQLObject) -> Option<String> {
         .and_then(|writer_content| String::from_utf8(writer_content).ok())
 }
 
-pub fn render_groups_to_yaml(groups: &mut GitQLObject) -> Option<String> {
-    let mut elements: Vec<serde_yaml::Value> = Vec::new();
-
-    if let Some(group) = groups.groups.first() {
-        let titles = &groups.titles;
-        for row in &group.rows {
-            let mut object = serde_yaml::Mapping::new();
-            for (i, value) in row.values.iter().enumerate() {
-                object.insert(
-                    serde_yaml::Value::String(titles[i].clone()),
-                    serde_yaml::Value::String(value.literal()),
-                );
-            }
-            elements.push(serde_yaml::Value::Mapping(object));
+/// GitHub-flavored pipe table.
+pub fn render_cells_to_markdown(titles: &[String], rows: &[Vec<String>]) -> String {
+    let escape = |cell: &str| cell.replace('|', "\\|").replace('\n', "<br>");
+    let line = |cells: Vec<String>| format!("| {} |\n", cells.join(" | "));
+
+    let mut markdown = line(titles.iter().map(|title| escape(title)).collect());
+    markdown.push_str(&line(titles.iter().map(|_| "---".to_string()).collect()));
+    for row in rows {
+        markdown.push_str(&line(row.iter().map(|cell| escape(cell)).collect()));
+    }
+    markdown
+}
+
+pub fn render_cells_to_html(titles: &[String], rows: &[Vec<String>]) -> String {
+    let mut html = String::from("<table>\n  <thead>\n    <tr>");
+    for title in titles {
+        html.push_str(&format!("<th>{}</th>", escape_html(title)));
+    }
+    html.push_str("</tr>\n  </thead>\n  <tbody>\n");
+    for row in rows {
+        html.push_str("    <tr>");
+        for cell in row {
+            html.push_str(&format!("<td>{}</td>", escape_html(cell)));
         }
+        html.push_str("</tr>\n");
     }
+    html.push_str("  </tbody>\n</table>\n");
+    html
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+pub fn render_groups_to_yaml(groups: &mut GitQLObject) -> Option<String> {
+    let (titles, rows) = flat_rows(groups);
+    let elements: Vec<serde_yaml::Value> =
+        rows.iter().map(|row| row_to_yaml(titles, row)).collect();
 
     serde_yaml::to_string(&elements).ok()
 }
+
+fn row_to_yaml(titles: &[String], row: &Row) -> serde_yaml::Value {
+    let mapping = titles
+        .iter()
+        .zip(&row.values)
+        .map(|(title, value)| {
+            (
+                serde_yaml::Value::String(title.clone()),
+                value_to_yaml(value.as_ref()),
+            )
+        })
+        .collect();
+    serde_yaml::Value::Mapping(mapping)
+}
+
+/// Convert a GitQL value to its native JSON type. Dates and datetimes become
+/// RFC 3339 strings, which is how JSON consumers expect to find them.
+fn value_to_json(value: &(dyn Value + 'static)) -> serde_json::Value {
+    if value.is_null() {
+        serde_json::Value::Null
+    } else if let Some(boolean) = value.as_bool() {
+        serde_json::Value::Bool(boolean)
+    } else if let Some(int) = value.as_int() {
+        serde_json::Value::from(int)
+    } else if let Some(float) = value.as_float() {
+        serde_json::Number::from_f64(float)
+            .map_or(serde_json::Value::Null, serde_json::Value::Number)
+    } else if let Some(date_time) = value.as_date_time() {
+        Utc.timestamp_opt(date_time, 0).single().map_or_else(
+            || serde_json::Value::String(value.literal()),
+            |date_time| serde_json::Value::String(date_time.to_rfc3339()),
+        )
+    } else if let Some(array) = value.as_array() {
+        serde_json::Value::Array(
+            array
+                .iter()
+                .map(|item| value_to_json(item.as_ref()))
+                .collect(),
+        )
+    } else {
+        // Dates render as `YYYY-MM-DD`, the RFC 3339 full-date form.
+        serde_json::Value::String(value.literal())
+    }
+}
+
+fn value_to_yaml(value: &(dyn Value + 'static)) -> serde_yaml::Value {
+    serde_yaml::to_value(value_to_json(value)).unwrap_or(serde_yaml::Value::Null)
+}
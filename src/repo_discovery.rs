@@ -0,0 +1,72 @@
+use gix::discover::repository::Kind;
+use std::path::{Path, PathBuf};
+
+/// Every git repository at or below `root`: checkouts, bare repositories and
+/// linked worktrees, in directory-name order.
+///
+/// `max_depth` counts directories below `root`, which is depth 0. Checkouts
+/// are searched too, so clones nested in a meta-repo are found, but their
+/// submodules are skipped unless `include_submodules` is set.
+pub fn discover_repositories(
+    root: &Path,
+    max_depth: Option<usize>,
+    include_submodules: bool,
+) -> Result<Vec<PathBuf>, String> {
+    if !root.is_dir() {
+        return Err(format!("{} is not a directory", root.display()));
+    }
+
+    let mut repositories = vec![];
+    visit(root, 0, max_depth, include_submodules, &mut repositories);
+    Ok(repositories)
+}
+
+fn visit(
+    dir: &Path,
+    depth: usize,
+    max_depth: Option<usize>,
+    include_submodules: bool,
+    repositories: &mut Vec<PathBuf>,
+) {
+    let dot_git = dir.join(".git");
+    if dot_git.exists() {
+        match gix::discover::is_git(&dot_git) {
+            Ok(Kind::WorkTree { .. }) => repositories.push(dir.to_path_buf()),
+            Ok(Kind::Submodule { .. }) if include_submodules => {
+                repositories.push(dir.to_path_buf())
+            }
+            Ok(Kind::Submodule { .. }) => return,
+            _ => {}
+        }
+    } else if let Ok(Kind::PossiblyBare) = gix::discover::is_git(dir) {
+        repositories.push(dir.to_path_buf());
+        return;
+    }
+
+    if max_depth.is_some_and(|max_depth| depth >= max_depth) {
+        return;
+    }
+
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    // Symlinked directories are skipped so a link back up the tree can't
+    // make the walk loop.
+    let mut subdirs: Vec<PathBuf> = entries
+        .flatten()
+        .filter(|entry| entry.file_type().is_ok_and(|file_type| file_type.is_dir()))
+        .filter(|entry| entry.file_name() != ".git")
+        .map(|entry| entry.path())
+        .collect();
+    subdirs.sort();
+
+    for subdir in subdirs {
+        visit(
+            &subdir,
+            depth + 1,
+            max_depth,
+            include_submodules,
+            repositories,
+        );
+    }
+}